@@ -0,0 +1,248 @@
+use crate::parse::Span;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Note,
+    Warning,
+    Error,
+}
+
+/*
+ * Codes are stable: once published a code keeps its meaning, new
+ * problems get new codes.
+ *
+ *  E00xx  lexing and section structure
+ *  E01xx  #[import] declarations
+ *  E02xx  #[content] lines
+ *  E03xx  neml.toml project manifest
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Code {
+    UnterminatedString,
+    UnknownSection,
+    OutsideSection,
+    InvalidUtf8,
+    ExpectedUse,
+    UnexpectedToken,
+    UnclosedGroup,
+    MissingAnchor,
+    UnresolvedImport,
+    UnknownAnchor,
+    ImportCycle,
+    GlobConflict,
+    PrivateAnchor,
+    UnusedImport,
+    DuplicateImport,
+    AliasConflict,
+    InvalidModulePath,
+    PathEscapesRoot,
+    OddIndent,
+    MissingName,
+    MissingTarget,
+    MixedIndent,
+    IndentJump,
+    BadDedent,
+    OrphanNode,
+    UnknownName,
+    AmbiguousName,
+    ShadowedName,
+    AttributeSyntax,
+    DuplicateAttribute,
+    DuplicateId,
+    DuplicatePath,
+    SlashInName,
+    ChainedRelation,
+    ManifestSyntax,
+    ManifestKey,
+    ManifestType,
+    ManifestMissing,
+    ManifestEntry,
+    ManifestUnreadable,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: Code,
+    pub message: String,
+    pub file: Option<String>,
+    pub span: Span,
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+    pub help: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct Diagnostics {
+    pub list: Vec<Diagnostic>,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Note => "note",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+impl Code {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Code::UnterminatedString => "E0001",
+            Code::UnknownSection => "E0002",
+            Code::OutsideSection => "E0003",
+            Code::InvalidUtf8 => "E0004",
+            Code::ExpectedUse => "E0101",
+            Code::UnexpectedToken => "E0102",
+            Code::UnclosedGroup => "E0103",
+            Code::MissingAnchor => "E0104",
+            Code::UnresolvedImport => "E0105",
+            Code::UnknownAnchor => "E0106",
+            Code::ImportCycle => "E0107",
+            Code::GlobConflict => "E0108",
+            Code::PrivateAnchor => "E0109",
+            Code::UnusedImport => "E0110",
+            Code::DuplicateImport => "E0111",
+            Code::AliasConflict => "E0112",
+            Code::InvalidModulePath => "E0113",
+            Code::PathEscapesRoot => "E0114",
+            Code::OddIndent => "E0201",
+            Code::MissingName => "E0202",
+            Code::MissingTarget => "E0203",
+            Code::MixedIndent => "E0204",
+            Code::IndentJump => "E0205",
+            Code::BadDedent => "E0206",
+            Code::OrphanNode => "E0207",
+            Code::UnknownName => "E0208",
+            Code::AmbiguousName => "E0209",
+            Code::ShadowedName => "E0210",
+            Code::AttributeSyntax => "E0211",
+            Code::DuplicateAttribute => "E0212",
+            Code::DuplicateId => "E0213",
+            Code::DuplicatePath => "E0214",
+            Code::SlashInName => "E0215",
+            Code::ChainedRelation => "E0216",
+            Code::ManifestSyntax => "E0301",
+            Code::ManifestKey => "E0302",
+            Code::ManifestType => "E0303",
+            Code::ManifestMissing => "E0304",
+            Code::ManifestEntry => "E0305",
+            Code::ManifestUnreadable => "E0306",
+        }
+    }
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, code: Code, message: impl Into<String>, span: Span) -> Self {
+        Diagnostic {
+            severity,
+            code,
+            message: message.into(),
+            file: None,
+            span,
+            labels: Vec::new(),
+            notes: Vec::new(),
+            help: None,
+        }
+    }
+
+    pub fn error(code: Code, message: impl Into<String>, span: Span) -> Self {
+        Diagnostic::new(Severity::Error, code, message, span)
+    }
+
+    pub fn warning(code: Code, message: impl Into<String>, span: Span) -> Self {
+        Diagnostic::new(Severity::Warning, code, message, span)
+    }
+
+    pub fn with_label(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.labels.push(Label {
+            span,
+            message: message.into(),
+        });
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}[{}]: {}",
+            self.severity.as_str(),
+            self.code.as_str(),
+            self.message
+        )?;
+
+        let file = self.file.as_deref().unwrap_or("<unknown>");
+        write!(f, "\n --> {}:{}:{}", file, self.span.line, self.span.column)?;
+
+        for label in &self.labels {
+            write!(
+                f,
+                "\n  = {}:{}:{}: {}",
+                file, label.span.line, label.span.column, label.message
+            )?;
+        }
+        for note in &self.notes {
+            write!(f, "\n  = note: {}", note)?;
+        }
+        if let Some(help) = &self.help {
+            write!(f, "\n  = help: {}", help)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Diagnostics {
+    pub fn new() -> Self {
+        Diagnostics { list: Vec::new() }
+    }
+
+    pub fn push(&mut self, diag: Diagnostic) {
+        self.list.push(diag);
+    }
+
+    pub fn error_count(&self) -> usize {
+        self.list.iter().filter(|d| d.is_error()).count()
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.list.iter().any(|d| d.is_error())
+    }
+
+    pub fn set_file(&mut self, file: &str) {
+        for d in &mut self.list {
+            if d.file.is_none() {
+                d.file = Some(file.to_string());
+            }
+        }
+    }
+
+    pub fn take(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.list)
+    }
+}
@@ -0,0 +1,8 @@
+pub mod diagnostic;
+pub use diagnostic::{Code, Diagnostic, Diagnostics, Label, Severity};
+
+pub mod source;
+pub use source::{SourceFile, SourceMap};
+
+pub mod render;
+pub use render::Renderer;
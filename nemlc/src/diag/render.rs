@@ -0,0 +1,262 @@
+use super::diagnostic::{Diagnostic, Severity};
+use super::source::{SourceFile, SourceMap};
+use crate::parse::Span;
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const BLUE: &str = "\x1b[1;34m";
+const CYAN: &str = "\x1b[1;36m";
+
+pub struct Renderer {
+    pub color: bool,
+}
+
+struct Mark<'a> {
+    span: Span,
+    primary: bool,
+    message: &'a str,
+}
+
+impl Renderer {
+    pub fn plain() -> Self {
+        Renderer { color: false }
+    }
+
+    pub fn colored() -> Self {
+        Renderer { color: true }
+    }
+
+    fn paint(&self, style: &str, text: &str) -> String {
+        if self.color {
+            format!("{}{}{}", style, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+
+    fn severity_style(severity: Severity) -> &'static str {
+        match severity {
+            Severity::Error => RED,
+            Severity::Warning => YELLOW,
+            Severity::Note => CYAN,
+        }
+    }
+
+    /*
+     *  error[E0102]: expected `,` or `}`, found `d`
+     *   --> demo.neml:3:12
+     *    |
+     *  3 | use a/b.{c d}
+     *    |            ^
+     *    |         - group opened here
+     *    = help: ...
+     */
+    pub fn render(&self, diag: &Diagnostic, sources: &SourceMap) -> String {
+        let style = Renderer::severity_style(diag.severity);
+        let file_name = diag.file.as_deref().unwrap_or("<unknown>");
+        let file = sources.get(file_name);
+
+        let mut marks = vec![Mark {
+            span: diag.span,
+            primary: true,
+            message: "",
+        }];
+        for label in &diag.labels {
+            marks.push(Mark {
+                span: label.span,
+                primary: false,
+                message: &label.message,
+            });
+        }
+
+        let mut lines: Vec<usize> = marks.iter().map(|m| m.span.line).collect();
+        lines.sort_unstable();
+        lines.dedup();
+
+        let width = lines.last().copied().unwrap_or(0).to_string().len();
+        let pad = " ".repeat(width);
+        let bar = self.paint(BLUE, "|");
+
+        let mut out = format!(
+            "{}{}\n{}{} {}:{}:{}\n",
+            self.paint(
+                style,
+                &format!("{}[{}]", diag.severity.as_str(), diag.code.as_str())
+            ),
+            self.paint(BOLD, &format!(": {}", diag.message)),
+            pad,
+            self.paint(BLUE, "-->"),
+            file_name,
+            diag.span.line,
+            diag.span.column
+        );
+
+        if let Some(file) = file {
+            out.push_str(&format!("{} {}\n", pad, bar));
+            for line_number in lines {
+                let text = match file.line(line_number) {
+                    Some(t) => t,
+                    None => continue,
+                };
+
+                out.push_str(&format!(
+                    "{} {} {}\n",
+                    self.paint(BLUE, &format!("{:>w$}", line_number, w = width)),
+                    bar,
+                    text
+                ));
+
+                for mark in marks.iter().filter(|m| m.span.line == line_number) {
+                    let (indent, len) = Renderer::underline(file, text, mark.span);
+                    let (ch, mark_style) = if mark.primary {
+                        ("^", style)
+                    } else {
+                        ("-", BLUE)
+                    };
+
+                    let mut under = self.paint(mark_style, &ch.repeat(len));
+                    if !mark.message.is_empty() {
+                        under.push(' ');
+                        under.push_str(&self.paint(mark_style, mark.message));
+                    }
+                    out.push_str(&format!("{} {} {}{}\n", pad, bar, indent, under));
+                }
+            }
+        } else {
+            for label in &diag.labels {
+                out.push_str(&format!(
+                    "{} {} {}:{}:{}: {}\n",
+                    pad,
+                    self.paint(BLUE, "="),
+                    file_name,
+                    label.span.line,
+                    label.span.column,
+                    label.message
+                ));
+            }
+        }
+
+        for note in &diag.notes {
+            out.push_str(&format!(
+                "{} {} {}: {}\n",
+                pad,
+                self.paint(BLUE, "="),
+                self.paint(BOLD, "note"),
+                note
+            ));
+        }
+        if let Some(help) = &diag.help {
+            out.push_str(&format!(
+                "{} {} {}: {}\n",
+                pad,
+                self.paint(BLUE, "="),
+                self.paint(BOLD, "help"),
+                help
+            ));
+        }
+
+        out
+    }
+
+    pub fn render_all(&self, diags: &[Diagnostic], sources: &SourceMap) -> String {
+        let mut out = String::new();
+        for d in diags {
+            out.push_str(&self.render(d, sources));
+            out.push('\n');
+        }
+
+        out
+    }
+
+    /*
+     * The whitespace before the marker copies tabs from the source line
+     * so the carets stay aligned however the terminal expands them, and
+     * wide (CJK, emoji) characters count as two columns.
+     */
+    fn underline(file: &SourceFile, text: &str, span: Span) -> (String, usize) {
+        let mut indent = String::new();
+        for c in text.chars().take(span.column.saturating_sub(1)) {
+            if c == '\t' {
+                indent.push('\t');
+            } else {
+                indent.push_str(&" ".repeat(Renderer::char_width(c)));
+            }
+        }
+
+        let line_start = file.line_offset(span.line).unwrap_or(0);
+        let start = span.start.saturating_sub(line_start).min(text.len());
+        let end = span
+            .end
+            .saturating_sub(line_start)
+            .min(text.len())
+            .max(start);
+        let len = text
+            .get(start..end)
+            .map_or(0, |s| s.chars().map(Renderer::char_width).sum());
+
+        (indent, len.max(1))
+    }
+
+    fn char_width(c: char) -> usize {
+        match c as u32 {
+            0x1100..=0x115F
+            | 0x2E80..=0x303E
+            | 0x3041..=0x33FF
+            | 0x3400..=0x4DBF
+            | 0x4E00..=0x9FFF
+            | 0xA000..=0xA4CF
+            | 0xAC00..=0xD7A3
+            | 0xF900..=0xFAFF
+            | 0xFE30..=0xFE4F
+            | 0xFF00..=0xFF60
+            | 0xFFE0..=0xFFE6
+            | 0x1F300..=0x1F64F
+            | 0x1F680..=0x1F6FF
+            | 0x1F900..=0x1F9FF
+            | 0x20000..=0x3FFFD => 2,
+            _ => 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn underline(text: &str, column: usize, start: usize, end: usize) -> (String, usize) {
+        let mut file = SourceFile::new("t.neml");
+        file.push_line(0, text);
+        Renderer::underline(&file, text, Span::new(start, end, 1, column))
+    }
+
+    #[test]
+    fn cjk_is_two_columns_wide() {
+        let (indent, len) = underline("服务 + 数据库", 6, 9, 18);
+        assert_eq!(indent, " ".repeat(7));
+        assert_eq!(len, 6);
+    }
+
+    #[test]
+    fn accents_are_one_column_wide() {
+        let (indent, len) = underline("café - naïve", 8, 8, 14);
+        assert_eq!(indent, " ".repeat(7));
+        assert_eq!(len, 5);
+    }
+
+    #[test]
+    fn emoji_is_two_columns_wide() {
+        let (indent, len) = underline("\t🚀 x", 4, 6, 7);
+        assert_eq!(indent, format!("\t{}", " ".repeat(3)));
+        assert_eq!(len, 1);
+
+        let (_, len) = underline("🚀🚀", 1, 0, 8);
+        assert_eq!(len, 4);
+    }
+
+    #[test]
+    fn empty_spans_still_get_a_marker() {
+        assert_eq!(underline("数据", 3, 6, 6), ("    ".to_string(), 1));
+    }
+}
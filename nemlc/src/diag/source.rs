@@ -0,0 +1,63 @@
+#[derive(Debug, Clone)]
+pub struct SourceFile {
+    pub name: String,
+    pub lines: Vec<String>,
+    pub offsets: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SourceMap {
+    pub files: Vec<SourceFile>,
+}
+
+impl SourceFile {
+    pub fn new(name: &str) -> Self {
+        SourceFile {
+            name: name.to_string(),
+            lines: Vec::new(),
+            offsets: Vec::new(),
+        }
+    }
+
+    pub fn push_line(&mut self, offset: usize, text: &str) {
+        self.offsets.push(offset);
+        self.lines.push(text.to_string());
+    }
+
+    pub fn line(&self, line_number: usize) -> Option<&str> {
+        if line_number == 0 {
+            return None;
+        }
+
+        self.lines.get(line_number - 1).map(|l| l.as_str())
+    }
+
+    pub fn line_offset(&self, line_number: usize) -> Option<usize> {
+        if line_number == 0 {
+            return None;
+        }
+
+        self.offsets.get(line_number - 1).copied()
+    }
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        SourceMap { files: Vec::new() }
+    }
+
+    pub fn add(&mut self, file: SourceFile) {
+        self.files.retain(|f| f.name != file.name);
+        self.files.push(file);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&SourceFile> {
+        self.files.iter().find(|f| f.name == name)
+    }
+
+    pub fn extend(&mut self, other: SourceMap) {
+        for f in other.files {
+            self.add(f);
+        }
+    }
+}
@@ -0,0 +1,428 @@
+use crate::diag::{Code, Diagnostic};
+use crate::parse::{Attribute, Scanner, Span};
+
+/*
+ * The attributes of a node or an edge, written in brackets after its
+ * name or target:
+ *
+ *   db [color=red, weight=3, ratio=0.5, hidden, tags=(a b "c d")]
+ *
+ * A key without a value is `true`. Bare words are strings unless they
+ * read as a boolean or a number; `(...)` is a list of values separated
+ * by spaces. Text that does not start with `[` is not parsed and only
+ * kept as the macro string.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttrValue {
+    Str(String),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    List(Vec<AttrValue>),
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Attrs {
+    pub entries: Vec<(String, AttrValue, Span)>,
+}
+
+const ESCAPES: &[(char, char)] = &[('"', '"'), ('\\', '\\')];
+
+struct Cursor<'a> {
+    scan: Scanner<'a>,
+}
+
+impl AttrValue {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            AttrValue::Str(_) => "a string",
+            AttrValue::Int(_) => "an integer",
+            AttrValue::Float(_) => "a float",
+            AttrValue::Bool(_) => "a boolean",
+            AttrValue::List(_) => "a list",
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            AttrValue::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            AttrValue::Int(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /*
+     * Integers widen to floats, so `weight=3` and `weight=3.0` read
+     * the same here.
+     */
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            AttrValue::Float(x) => Some(*x),
+            AttrValue::Int(n) => Some(*n as f64),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            AttrValue::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[AttrValue]> {
+        match self {
+            AttrValue::List(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    fn from_word(word: &str) -> AttrValue {
+        match word {
+            "true" => return AttrValue::Bool(true),
+            "false" => return AttrValue::Bool(false),
+            _ => {}
+        }
+
+        let digits = word.strip_prefix(['-', '+']).unwrap_or(word);
+        if !digits.starts_with(|c: char| c.is_ascii_digit()) {
+            return AttrValue::Str(word.to_string());
+        }
+        if let Ok(n) = word.parse::<i64>() {
+            return AttrValue::Int(n);
+        }
+        match word.parse::<f64>() {
+            Ok(x) => AttrValue::Float(x),
+            Err(_) => AttrValue::Str(word.to_string()),
+        }
+    }
+}
+
+impl Attrs {
+    pub fn get(&self, key: &str) -> Option<&AttrValue> {
+        self.entries
+            .iter()
+            .find(|(k, _, _)| k == key)
+            .map(|(_, v, _)| v)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &AttrValue)> {
+        self.entries.iter().map(|(k, v, _)| (k.as_str(), v))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /*
+     * Parses the attribute text of a node or a relation. Problems are
+     * pushed to `errors` and the attributes read so far are kept.
+     */
+    pub fn parse(attribute: &Attribute, errors: &mut Vec<Diagnostic>) -> Attrs {
+        let mut attrs = Attrs::default();
+        if !attribute.text.starts_with('[') {
+            return attrs;
+        }
+
+        let mut cur = Cursor {
+            scan: Scanner::new(&attribute.text, attribute.span),
+        };
+        cur.scan.pos = 1;
+
+        if let Err(d) = attrs.parse_pairs(&mut cur, errors) {
+            errors.push(d);
+        }
+
+        attrs
+    }
+
+    fn parse_pairs(
+        &mut self,
+        cur: &mut Cursor,
+        errors: &mut Vec<Diagnostic>,
+    ) -> Result<(), Diagnostic> {
+        loop {
+            cur.skip();
+            match cur.scan.peek() {
+                Some(']') => {
+                    cur.scan.pos += 1;
+                    break;
+                }
+                None => {
+                    return Err(cur
+                        .scan
+                        .error(Code::AttributeSyntax, 0, "unclosed `[`")
+                        .with_help("add a closing `]`"))
+                }
+                _ => {}
+            }
+
+            let start = cur.scan.pos;
+            let key = cur.word();
+            if key.is_empty() {
+                cur.scan.pos += 1;
+                return Err(cur.scan.error(
+                    Code::AttributeSyntax,
+                    start,
+                    "expected an attribute name",
+                ));
+            }
+            let key_span = cur.scan.span_from(start);
+
+            cur.skip();
+            let value = match cur.scan.peek() {
+                Some('=') => {
+                    cur.scan.pos += 1;
+                    cur.skip();
+                    cur.value()?
+                }
+                _ => AttrValue::Bool(true),
+            };
+
+            if let Some((_, _, first)) = self.entries.iter().find(|(k, _, _)| *k == key) {
+                errors.push(
+                    Diagnostic::warning(
+                        Code::DuplicateAttribute,
+                        format!("attribute `{}` is set twice", key),
+                        key_span,
+                    )
+                    .with_label(*first, "first set here")
+                    .with_help("the last value is used"),
+                );
+                self.entries.retain(|(k, _, _)| *k != key);
+            }
+            self.entries.push((key, value, key_span));
+
+            cur.skip();
+            match cur.scan.peek() {
+                Some(',') => cur.scan.pos += 1,
+                Some(']') => {}
+                Some(c) => {
+                    let at = cur.scan.pos;
+                    cur.scan.pos += 1;
+                    return Err(cur.scan.error(
+                        Code::AttributeSyntax,
+                        at,
+                        format!("expected `,` or `]`, found `{}`", c),
+                    ));
+                }
+                None => {}
+            }
+        }
+
+        cur.skip();
+        if cur.scan.peek().is_some() {
+            let start = cur.scan.pos;
+            cur.scan.pos = cur.scan.chars.len();
+            return Err(cur
+                .scan
+                .error(
+                    Code::AttributeSyntax,
+                    start,
+                    "unexpected text after attributes",
+                )
+                .with_help("move it inside the brackets"));
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> Cursor<'a> {
+    fn skip(&mut self) {
+        self.scan.eat_while(char::is_whitespace);
+    }
+
+    fn word(&mut self) -> String {
+        self.scan
+            .eat_while(|c| {
+                !c.is_whitespace() && !matches!(c, '=' | ',' | '[' | ']' | '(' | ')' | '"')
+            })
+            .to_string()
+    }
+
+    fn value(&mut self) -> Result<AttrValue, Diagnostic> {
+        let start = self.scan.pos;
+        match self.scan.peek() {
+            Some('"') => {
+                Ok(AttrValue::Str(self.scan.string(ESCAPES, true).map_err(
+                    |e| e.to_diagnostic(Code::AttributeSyntax, &self.scan),
+                )?))
+            }
+            Some('(') => {
+                self.scan.pos += 1;
+                let mut items = Vec::new();
+                loop {
+                    self.skip();
+                    match self.scan.peek() {
+                        Some(')') => {
+                            self.scan.pos += 1;
+                            return Ok(AttrValue::List(items));
+                        }
+                        None | Some(']') => {
+                            return Err(self
+                                .scan
+                                .error(Code::AttributeSyntax, start, "unclosed `(`")
+                                .with_help("add a closing `)`"))
+                        }
+                        _ => items.push(self.value()?),
+                    }
+                }
+            }
+            _ => {
+                let word = self.word();
+                if word.is_empty() {
+                    let found = match self.scan.peek() {
+                        Some(c) => format!("`{}`", c),
+                        None => "the end of the attributes".to_string(),
+                    };
+                    self.scan.pos += 1;
+                    return Err(self.scan.error(
+                        Code::AttributeSyntax,
+                        start,
+                        format!("expected a value, found {}", found),
+                    ));
+                }
+
+                Ok(AttrValue::from_word(&word))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> (Attrs, Vec<Diagnostic>) {
+        let attribute = Attribute {
+            text: text.to_string(),
+            span: Span::new(10, 10 + text.len(), 1, 11),
+        };
+        let mut errors = Vec::new();
+        let attrs = Attrs::parse(&attribute, &mut errors);
+        (attrs, errors)
+    }
+
+    fn ok(text: &str) -> Attrs {
+        let (attrs, errors) = parse(text);
+        assert!(errors.is_empty(), "{:?}", errors);
+        attrs
+    }
+
+    fn error(text: &str) -> Diagnostic {
+        let (_, mut errors) = parse(text);
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        errors.remove(0)
+    }
+
+    #[test]
+    fn values() {
+        let attrs = ok(r#"[name="a \"b\" \\", n=3, m=-2, x=0.5, on=true, off=false, word=red]"#);
+        assert_eq!(
+            attrs.get("name"),
+            Some(&AttrValue::Str("a \"b\" \\".into()))
+        );
+        assert_eq!(attrs.get("n"), Some(&AttrValue::Int(3)));
+        assert_eq!(attrs.get("m"), Some(&AttrValue::Int(-2)));
+        assert_eq!(attrs.get("x"), Some(&AttrValue::Float(0.5)));
+        assert_eq!(attrs.get("on"), Some(&AttrValue::Bool(true)));
+        assert_eq!(attrs.get("off"), Some(&AttrValue::Bool(false)));
+        assert_eq!(attrs.get("word"), Some(&AttrValue::Str("red".into())));
+        assert_eq!(attrs.get("n").and_then(AttrValue::as_float), Some(3.0));
+    }
+
+    #[test]
+    fn lists() {
+        let attrs = ok(r#"[tags=(a 2 "c d" (e))]"#);
+        assert_eq!(
+            attrs.get("tags"),
+            Some(&AttrValue::List(vec![
+                AttrValue::Str("a".into()),
+                AttrValue::Int(2),
+                AttrValue::Str("c d".into()),
+                AttrValue::List(vec![AttrValue::Str("e".into())]),
+            ]))
+        );
+        assert_eq!(ok("[tags=()]").get("tags"), Some(&AttrValue::List(vec![])));
+    }
+
+    #[test]
+    fn bare_keys_are_true() {
+        let attrs = ok("[hidden, wide]");
+        assert_eq!(attrs.get("hidden"), Some(&AttrValue::Bool(true)));
+        assert_eq!(attrs.get("wide"), Some(&AttrValue::Bool(true)));
+        assert_eq!(attrs.len(), 2);
+    }
+
+    #[test]
+    fn text_without_brackets_is_not_parsed() {
+        assert!(ok("+ b").is_empty());
+    }
+
+    #[test]
+    fn duplicate_keys_keep_the_last_value() {
+        let (attrs, errors) = parse("[n=1, m=2, n=3]");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].code, Code::DuplicateAttribute);
+        assert_eq!(errors[0].span, Span::new(21, 22, 1, 22));
+        assert_eq!(errors[0].labels[0].span, Span::new(11, 12, 1, 12));
+        assert_eq!(
+            attrs.iter().collect::<Vec<_>>(),
+            vec![("m", &AttrValue::Int(2)), ("n", &AttrValue::Int(3))]
+        );
+    }
+
+    #[test]
+    fn unclosed_bracket() {
+        let d = error("[a=1, b");
+        assert_eq!(d.code, Code::AttributeSyntax);
+        assert_eq!(d.message, "unclosed `[`");
+        assert_eq!(d.span.start, 10);
+    }
+
+    #[test]
+    fn unclosed_list() {
+        let d = error("[tags=(a b]");
+        assert_eq!(d.message, "unclosed `(`");
+        assert_eq!(d.span.start, 16);
+    }
+
+    #[test]
+    fn unterminated_string() {
+        let d = error(r#"[a="b]"#);
+        assert_eq!(d.message, "unterminated string");
+        assert_eq!(d.span.start, 13);
+        assert!(d.help.is_some());
+    }
+
+    #[test]
+    fn unknown_escape() {
+        assert_eq!(error(r#"[a="\n"]"#).message, "unknown escape");
+    }
+
+    #[test]
+    fn trailing_text() {
+        let (attrs, errors) = parse("[a=1] b");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message, "unexpected text after attributes");
+        assert_eq!(errors[0].span, Span::new(16, 17, 1, 17));
+        assert_eq!(attrs.get("a"), Some(&AttrValue::Int(1)));
+    }
+
+    #[test]
+    fn missing_separator() {
+        let d = error("[a=1 b=2]");
+        assert_eq!(d.message, "expected `,` or `]`, found `b`");
+    }
+}
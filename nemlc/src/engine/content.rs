@@ -1,11 +1,11 @@
 use super::engine::LineContext;
-use crate::engine::NeiEndpoint;
-use crate::utils::Finder;
-use regex::Regex;
+use crate::diag::{Code, Diagnostic, Diagnostics, Severity};
+use crate::engine::{AttrValue, Attrs, Edge, IndentStyle, Indentation, NeiEndpoint};
+use crate::parse::{Attribute, Node, Relation, RelationOp, Span, Token, TokenKind};
 use std::cell::RefCell;
 use std::rc::Rc;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Default)]
 pub struct Item {
     pub level: usize,
     pub name: Option<String>,
@@ -18,6 +18,16 @@ pub struct Item {
     pub root: usize,
 
     pub sub_list: Vec<usize>,
+    pub span: Span,
+    pub name_span: Span,
+    pub public: bool,
+    pub target_span: Span,
+    pub label: Option<String>,
+    pub rel_span: Span,
+    pub attrs: Attrs,
+    pub rel_attrs: Attrs,
+    pub path: String,
+    pub id: Option<String>,
 }
 
 #[derive(Debug)]
@@ -27,6 +37,8 @@ pub struct Entity {
     pub attr: Option<String>,
     pub level: usize,
     pub root: usize,
+    pub body_span: Span,
+    pub attr_span: Span,
 }
 
 #[derive(Debug, PartialEq)]
@@ -38,127 +50,178 @@ pub enum ItemStatus {
 #[derive(Debug)]
 pub struct Content {
     pub current_level: usize,
+    pub indent: Indentation,
+    pub lenient: bool,
+    pub dropped: Vec<(Span, usize)>,
     pub data_list: Rc<RefCell<Vec<Item>>>,
+    pub edges: Vec<Edge>,
 }
 
 pub struct LevelTable {
     pub table: Vec<usize>,
-    pub current_level: usize,
+    pub levels: Vec<usize>,
 }
 
 impl Content {
     pub fn init() -> Self {
         let c = Content {
             current_level: 0,
+            indent: Indentation::new(IndentStyle::Spaces(2), false),
+            lenient: false,
+            dropped: Vec::new(),
             data_list: Rc::new(RefCell::new(Vec::new())),
+            edges: Vec::new(),
         };
 
-        let item = Item {
-            name: None,
-            name_macro: None,
-            dir: None,
-            relation: None,
-            rel_macro: None,
-            level: 0,
-            index: 0,
-            root: 0,
-            sub_list: Vec::new(),
-        };
-
-        c.data_list.borrow_mut().push(item);
+        c.data_list.borrow_mut().push(Item::default());
 
         c
     }
 
     /*
-     *  |index| self|root |  0  |  1  |  2  |  3  |
-     *  |  0  |  -  |  -  |  0  |  -  |  -  |  -  |
-     *  |  1  |  0  |  0  |  0  |  1  |  -  |  -  |
-     *  |  2  |  1  |  1  |  0  |  1  |  2  |  -  |
-     *  |  3  |  2  |  2  |  0  |  1  |  2  |  2  |
-     *  |  4  |  1  |  1  |  0  |  1  |  4  |  -  |
-     *  |  5  |  2  |  4  |  0  |  1  |  4  |  5  |
+     * `table` holds the open ancestors of the next item, outermost
+     * first, with the root at 0. `levels` holds their indentation level
+     * plus one so the root sits below every real level.
+     *
+     *   a          table [0, a]        levels [0, 1]
+     *     b        table [0, a, b]     levels [0, 1, 2]
+     *   c          table [0, c]        levels [0, 1]
+     *
+     * A node that skips a level, dedents to a level no ancestor uses, or
+     * lost its parent line is attached to the nearest ancestor anyway so
+     * later lines still resolve; it is an error unless `lenient` is set.
      */
-    pub fn parse_item_level(&mut self, index: usize, table: &mut LevelTable) -> usize {
-        let data_table = &mut self.data_list.borrow_mut();
-        let ent = &mut data_table[index];
-
-        if ent.level > table.current_level {
-            return 0;
+    pub fn parse_item_level(
+        &mut self,
+        index: usize,
+        table: &mut LevelTable,
+        diag: &mut Diagnostics,
+    ) -> usize {
+        let data_table = self.data_list.borrow();
+        let ent = &data_table[index];
+        let key = ent.level + 1;
+
+        let mut dedent = false;
+        while table.levels.last().is_some_and(|&l| l > key) {
+            table.levels.pop();
+            table.table.pop();
+            dedent = true;
         }
 
-        if table.table.len() != (table.current_level + 1) {
-            // TODO: Error
+        if table.levels.last() == Some(&key) {
+            table.levels.pop();
+            table.table.pop();
         }
 
-        let root_index = table.table[ent.level];
+        let root_index = table.table.last().copied().unwrap_or(0);
+        let root_key = table.levels.last().copied().unwrap_or(0);
+        let parent = &data_table[root_index];
+        let misplaced = root_key + 1 != key;
+        let dropped = self.dropped_parent(ent, parent);
 
-        if (ent.level + 1) > table.current_level {
-            table.table.push(ent.index);
-        } else {
-            table.table[ent.level + 1] = ent.index;
-            table.table.truncate(ent.level + 2);
+        if misplaced || dropped.is_some() {
+            diag.push(self.level_error(ent, parent, root_key, dedent && misplaced, dropped));
         }
 
-        table.current_level = ent.level + 1;
+        table.table.push(index);
+        table.levels.push(key);
 
-        return root_index;
+        root_index
     }
 
-    pub fn parse_root_item(&mut self, s: &String) -> Result<Item, String> {
-        let tokens = s.split(" ");
-        let tc: Vec<&str> = tokens.collect();
-        let tc_len = tc.len();
-        if tc_len > 2 {
-            return Err(String::from("Invalid"));
-        }
-
-        let name = tc[0].trim().to_string();
-        if tc_len == 1 {
-            let item = Item {
-                level: 0,
-                name: Some(name),
-                name_macro: None,
-                dir: None,
-                relation: None,
-                rel_macro: None,
-                index: 0,
-                root: 0,
-                sub_list: Vec::new(),
-            };
+    /*
+     * The last line between `parent` and `ent` that failed to parse at
+     * a level above `ent`: had it parsed, `ent` would sit below it.
+     */
+    fn dropped_parent(&self, ent: &Item, parent: &Item) -> Option<Span> {
+        self.dropped
+            .iter()
+            .rev()
+            .find(|(span, level)| {
+                span.start < ent.span.start && span.start >= parent.span.end && *level < ent.level
+            })
+            .map(|&(span, _)| span)
+    }
 
-            return Ok(item);
+    fn level_error(
+        &self,
+        ent: &Item,
+        parent: &Item,
+        parent_key: usize,
+        dedent: bool,
+        dropped: Option<Span>,
+    ) -> Diagnostic {
+        let name = ent.name.as_deref().unwrap_or("");
+        let parent_name = parent.name.as_deref().unwrap_or("");
+
+        let mut d = if dedent {
+            Diagnostic::new(
+                self.level_severity(),
+                Code::BadDedent,
+                format!("`{}` dedents to a level no enclosing node uses", name),
+                ent.span,
+            )
+            .with_help(format!(
+                "dedent `{}` to line it up with the children of `{}`",
+                name, parent_name
+            ))
+        } else if parent.index == 0 || dropped.is_some() {
+            Diagnostic::new(
+                self.level_severity(),
+                Code::OrphanNode,
+                format!("`{}` is indented but has no parent node", name),
+                ent.span,
+            )
         } else {
-            let name_macro = tc[1].trim().to_string();
-            let item = Item {
-                level: 0,
-                name: Some(name),
-                name_macro: Some(name_macro),
-                dir: None,
-                relation: None,
-                rel_macro: None,
-                index: 0,
-                root: 0,
-                sub_list: Vec::new(),
-            };
+            Diagnostic::new(
+                self.level_severity(),
+                Code::IndentJump,
+                format!(
+                    "`{}` is indented {} levels below its parent",
+                    name,
+                    ent.level + 1 - parent_key
+                ),
+                ent.span,
+            )
+            .with_help(format!(
+                "indent `{}` exactly one level deeper than `{}`",
+                name, parent_name
+            ))
+        };
 
-            return Ok(item);
+        if let Some(span) = dropped {
+            d = d.with_label(span, "its parent line failed to parse");
         }
-    }
 
-    pub fn element_parse(&mut self, s: &String) -> Option<Entity> {
-        let new_s = s.trim().to_string();
+        if parent.index == 0 {
+            d = d.with_help("remove the indentation or add a parent line above it");
+        } else {
+            d = d.with_label(
+                parent.span,
+                format!("nearest parent `{}` is here", parent_name),
+            );
+        }
 
-        let ai = Finder::next_point(&new_s, 0, ' ', true);
-        let ai_index = ai.unwrap();
+        if self.lenient {
+            d = d.with_note(match parent.index {
+                0 => "attached to the root".to_string(),
+                _ => format!("attached to `{}`", parent_name),
+            });
+        }
 
-        let body = &new_s[..ai_index].trim().to_string();
-        let mut attr: String = "".to_string();
+        d
+    }
 
-        if body.len() < new_s.len() {
-            let attr_t = &new_s[body.len()..].trim().to_string();
-            attr = attr_t.trim().to_string();
+    fn level_severity(&self) -> Severity {
+        if self.lenient {
+            Severity::Warning
+        } else {
+            Severity::Error
         }
+    }
+
+    pub fn element_parse(&mut self, linec: &LineContext, tokens: &[Token]) -> Option<Entity> {
+        let first = tokens.first()?;
 
         let mut a = Entity {
             body: None,
@@ -166,116 +229,256 @@ impl Content {
             index: 0,
             root: 0,
             level: 0,
+            body_span: first.span,
+            attr_span: Span::default(),
+        };
+
+        let body = match first.text() {
+            Some(b) => b.trim().to_string(),
+            None => linec.slice(first.span).to_string(),
         };
 
-        if body.len() > 0 {
-            a.body = Some(body.to_string());
+        if !body.is_empty() {
+            a.body = Some(body);
         }
 
-        if attr.len() > 0 {
-            a.attr = Some(attr);
+        if let (Some(second), Some(last)) = (tokens.get(1), tokens.last()) {
+            let attr_span = second.span.to(last.span);
+            let attr = linec.slice(attr_span).trim().to_string();
+            if !attr.is_empty() {
+                a.attr = Some(attr);
+                a.attr_span = attr_span;
+            }
         }
 
         Some(a)
     }
 
-    pub fn parse_plain(&mut self, s: &String) -> Result<Vec<Item>, std::io::Error> {
-        let mut out_data = Vec::new();
+    pub fn parse_plain(
+        &mut self,
+        linec: &LineContext,
+        tokens: &[Token],
+        diag: &mut Diagnostics,
+    ) -> Result<Option<Node>, Diagnostic> {
+        let mut rest = tokens;
+        let mut level = 0;
+
+        if let Some(t) = tokens
+            .iter()
+            .find(|t| matches!(t.kind, TokenKind::Invalid(_)))
+        {
+            return Err(
+                Diagnostic::error(Code::UnterminatedString, "unterminated string", t.span)
+                    .with_help("add a closing `\"`"),
+            );
+        }
 
-        if !s.starts_with("  ") {
-            let root = self.parse_root_item(s).unwrap();
+        if let Some(Token {
+            kind: TokenKind::Indent(text),
+            span,
+        }) = rest.first()
+        {
+            level = self.indent.level(text, *span, diag)?;
+            rest = &rest[1..];
+        }
 
-            self.current_level = 0;
+        if rest.is_empty() {
+            return Ok(None);
+        }
 
-            out_data.push(root);
-        } else {
-            let mut level = Finder::space_count(s.as_str(), ' ', true);
+        self.current_level = level;
+
+        let mut public = false;
+        if rest.len() > 1
+            && rest[0].kind == TokenKind::Ident("pub".to_string())
+            && !rest[1].is_relation()
+        {
+            public = true;
+            rest = &rest[1..];
+        }
+
+        let span = rest[0].span.to(rest[rest.len() - 1].span);
+        let op = Content::relation_op(linec, rest);
+        let (name_part, mut rel_part) = match op {
+            Some(i) => (&rest[..i], &rest[(i + 1)..]),
+            None => (rest, &rest[rest.len()..]),
+        };
 
-            if (level % 2) != 0 {
-                return Ok(out_data);
+        /* `a + b + c`: only the first relation is kept */
+        if let (Some(i), Some(j)) = (op, Content::relation_op(linec, rel_part).filter(|&j| j > 0)) {
+            let second = &rel_part[j];
+            diag.push(
+                Diagnostic::error(
+                    Code::ChainedRelation,
+                    format!(
+                        "`{}` starts a second relation on one line",
+                        linec.slice(second.span)
+                    ),
+                    second.span.to(rel_part[rel_part.len() - 1].span),
+                )
+                .with_label(rest[i].span, "the line's relation")
+                .with_help("give each relation a line of its own"),
+            );
+            rel_part = &rel_part[..j];
+        }
+
+        let name_entity = match self.element_parse(linec, name_part) {
+            Some(e) => e,
+            None => {
+                return Err(Diagnostic::error(
+                    Code::MissingName,
+                    "relation without a node name",
+                    rest[0].span,
+                )
+                .with_help("write the node name before the relation operator"))
             }
+        };
 
-            level = level / 2;
-
-            self.current_level = level;
-
-            let r = Regex::new(r"(.*)[\+\-\*](.*)").unwrap();
-            if !r.is_match(s) {
-                let raw_name = s.trim().to_string();
-                let name_entity = self.element_parse(&raw_name).unwrap();
-
-                let item = Item {
-                    level: level,
-                    name: name_entity.body,
-                    name_macro: name_entity.attr,
-                    dir: None,
-                    relation: None,
-                    rel_macro: None,
-                    index: 0,
-                    root: 0,
-                    sub_list: Vec::new(),
-                };
-
-                out_data.push(item);
-            } else {
-                let mut count: usize = 0;
-                let mut tmp_data = Vec::new();
-                for (_i, c) in r.captures_iter(&s).enumerate() {
-                    for j in 1..c.len() {
-                        count += 1;
-                        tmp_data.push(c[j].trim().to_string());
-                    }
+        let name = match name_entity.body {
+            Some(n) => n,
+            None => {
+                return Err(Diagnostic::error(
+                    Code::MissingName,
+                    "empty node name",
+                    name_entity.body_span,
+                ))
+            }
+        };
+
+        let mut node = Node {
+            level,
+            name,
+            name_span: name_entity.body_span,
+            public,
+            attribute: name_entity.attr.map(|text| Attribute {
+                text,
+                span: name_entity.attr_span,
+            }),
+            relation: None,
+            span,
+        };
+
+        if let Some(i) = op {
+            /* `a + b "label" rest`: a string right after the target labels the edge */
+            let mut label = None;
+            let mut rel_tokens = rel_part.to_vec();
+            if let Some(Token {
+                kind: TokenKind::Str(text),
+                span,
+            }) = rel_part.get(1)
+            {
+                label = Some(Attribute {
+                    text: text.clone(),
+                    span: *span,
+                });
+                rel_tokens.remove(1);
+            }
+
+            let attr_entity = match self.element_parse(linec, &rel_tokens) {
+                Some(e) => e,
+                None => {
+                    return Err(Diagnostic::error(
+                        Code::MissingTarget,
+                        format!("relation `{}` without a target", linec.slice(rest[i].span)),
+                        rest[i].span,
+                    )
+                    .with_help("write the target node after the relation operator"))
                 }
+            };
 
-                if count == 2 {
-                    let raw_name = tmp_data[0].trim().to_string();
-                    let raw_rel = tmp_data[1].trim().to_string();
+            let target = match attr_entity.body {
+                Some(t) => t,
+                None => {
+                    return Err(Diagnostic::error(
+                        Code::MissingTarget,
+                        "empty relation target",
+                        attr_entity.body_span,
+                    ))
+                }
+            };
 
-                    if raw_name.len() == 0 || raw_rel.len() == 0 {
-                        return Ok(out_data);
-                    }
+            let op_kind = match rest[i].kind {
+                TokenKind::Plus => RelationOp::Plus,
+                TokenKind::Minus => RelationOp::Minus,
+                _ => RelationOp::Star,
+            };
 
-                    let r = Regex::new(r"[ ][\+\-\*][ ]").unwrap();
-                    if !r.is_match(s) {
-                        return Ok(out_data);
-                    }
+            node.relation = Some(Relation {
+                op: op_kind,
+                op_span: rest[i].span,
+                target,
+                target_span: attr_entity.body_span,
+                label,
+                attribute: attr_entity.attr.map(|text| Attribute {
+                    text,
+                    span: attr_entity.attr_span,
+                }),
+                span: rest[i].span.to(span),
+            });
+        }
 
-                    let mut dir: String = "".to_string();
+        Ok(Some(node))
+    }
 
-                    for (_i, c) in r.captures_iter(&s).enumerate() {
-                        for j in 0..c.len() {
-                            dir = c[j].trim().to_string();
-                            break;
+    /*
+     * The first relation operator outside `[...]`, so a `-` inside
+     * attributes such as `[tags=(a - b)]` is left alone. Brackets are
+     * counted in the source text, where an escaped `\[` is no bracket.
+     */
+    pub fn relation_op(linec: &LineContext, tokens: &[Token]) -> Option<usize> {
+        let mut depth: i32 = 0;
+        for (i, t) in tokens.iter().enumerate() {
+            if depth == 0 && t.is_relation() {
+                return Some(i);
+            }
+            if let TokenKind::Ident(_) = &t.kind {
+                let mut chars = linec.slice(t.span).chars();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => {
+                            chars.next();
                         }
+                        '[' => depth += 1,
+                        ']' => depth -= 1,
+                        _ => {}
                     }
-                    let name_entity = self.element_parse(&raw_name).unwrap();
-                    let attr_entity = self.element_parse(&raw_rel).unwrap();
-
-                    let item = Item {
-                        level: level,
-                        name: name_entity.body,
-                        name_macro: name_entity.attr,
-                        dir: Some(dir),
-                        relation: attr_entity.body,
-                        rel_macro: attr_entity.attr,
-                        index: 0,
-                        root: 0,
-                        sub_list: Vec::new(),
-                    };
-
-                    out_data.push(item);
-                } else {
-                    println!("error count Error");
                 }
             }
         }
 
-        Ok(out_data)
+        None
     }
 
-    pub fn extract(&mut self, s: &String) -> Result<Vec<Item>, std::io::Error> {
-        let ptc = self.parse_plain(s);
-        ptc
+    pub fn item_from_node(node: &Node) -> Item {
+        let mut item = Item {
+            level: node.level,
+            name: Some(node.name.clone()),
+            name_macro: node.attribute.as_ref().map(|a| a.text.clone()),
+            span: node.span,
+            name_span: node.name_span,
+            public: node.public,
+            ..Default::default()
+        };
+
+        if let Some(rel) = &node.relation {
+            item.dir = Some(rel.op.as_str().to_string());
+            item.relation = Some(rel.target.clone());
+            item.target_span = rel.target_span;
+            item.rel_macro = rel.attribute.as_ref().map(|a| a.text.clone());
+            item.label = rel.label.as_ref().map(|l| l.text.clone());
+            item.rel_span = rel.span;
+        }
+
+        item
+    }
+
+    pub fn extract(
+        &mut self,
+        linec: &LineContext,
+        tokens: &[Token],
+        diag: &mut Diagnostics,
+    ) -> Result<Option<Node>, Diagnostic> {
+        self.parse_plain(linec, tokens, diag)
     }
 
     pub fn update_sub_list(&mut self, root_index: usize, sub_index: usize) {
@@ -306,6 +509,7 @@ impl Content {
     ) -> NeiEndpoint {
         let mut end = NeiEndpoint::new(index, name);
         let item = &self.data_list.borrow()[index];
+        end.attrs = item.attrs.clone();
 
         for i in &item.sub_list {
             if processed[*i] == 1 {
@@ -344,18 +548,116 @@ impl Content {
         end
     }
 
-    pub fn parse_level(&mut self) {
+    /*
+     * One edge per relation, in source order. Run after `parse_level`
+     * so every item knows its index.
+     */
+    pub fn parse_edges(&mut self) {
+        self.edges = self
+            .data_list
+            .borrow()
+            .iter()
+            .skip(1)
+            .filter_map(Edge::from_item)
+            .collect();
+    }
+
+    /*
+     * Gives every node its qualified path, the names from the top level
+     * down joined with `/`, and the id set with `[id=...]`. Ids must be
+     * unique in a document; two siblings sharing a name share a path,
+     * and only the first is found by it. A name holding `/` would read
+     * as a path, so it is an error and that node and the nodes below it
+     * get no path.
+     */
+    pub fn parse_paths(&mut self, diag: &mut Diagnostics) {
+        let mut items = self.data_list.borrow_mut();
+        for index in 1..items.len() {
+            let name = items[index].name.clone().unwrap_or_default();
+            let root = items[index].root;
+            if root != 0 && items[root].path.is_empty() {
+                continue;
+            }
+            if name.contains('/') {
+                diag.push(
+                    Diagnostic::error(
+                        Code::SlashInName,
+                        format!("node name `{}` contains `/`", name),
+                        items[index].name_span,
+                    )
+                    .with_help("`/` separates the names of a path; use another character"),
+                );
+                continue;
+            }
+
+            let parent = &items[root].path;
+            items[index].path = match parent.is_empty() {
+                true => name,
+                false => format!("{}/{}", parent, name),
+            };
+
+            let first = (1..index).find(|&i| items[i].path == items[index].path);
+            if let Some(first) = first {
+                diag.push(
+                    Diagnostic::warning(
+                        Code::DuplicatePath,
+                        format!("`{}` is defined twice at the same level", items[index].path),
+                        items[index].name_span,
+                    )
+                    .with_label(items[first].name_span, "first defined here")
+                    .with_help("rename one of them or give it an `[id=...]`"),
+                );
+            }
+
+            let (value, span) = match items[index]
+                .attrs
+                .entries
+                .iter()
+                .find(|(k, _, _)| k == "id")
+            {
+                Some((_, value, span)) => (value.clone(), *span),
+                None => continue,
+            };
+            let id = match value {
+                AttrValue::Str(id) => id,
+                other => {
+                    diag.push(
+                        Diagnostic::error(
+                            Code::AttributeSyntax,
+                            format!("`id` must be a name, found {}", other.describe()),
+                            span,
+                        )
+                        .with_help("write the id as a word or a quoted string"),
+                    );
+                    continue;
+                }
+            };
+
+            if let Some(first) = (1..index).find(|&i| items[i].id.as_deref() == Some(id.as_str())) {
+                diag.push(
+                    Diagnostic::error(
+                        Code::DuplicateId,
+                        format!("id `{}` is used by two nodes", id),
+                        span,
+                    )
+                    .with_label(items[first].name_span, "already the id of this node"),
+                );
+                continue;
+            }
+            items[index].id = Some(id);
+        }
+    }
+
+    pub fn parse_level(&mut self, diag: &mut Diagnostics) {
         let mut table = LevelTable {
-            table: Vec::new(),
-            current_level: 0,
+            table: vec![0],
+            levels: vec![0],
         };
 
-        table.table.push(0);
-
         let mut index = 1;
         while index < self.data_list.borrow().len() {
             self.data_list.borrow_mut()[index].index = index;
-            let root_index = self.parse_item_level(index, &mut table);
+            let root_index = self.parse_item_level(index, &mut table, diag);
             self.data_list.borrow_mut()[index].root = root_index;
 
             index += 1;
@@ -363,34 +665,230 @@ impl Content {
     }
 
     pub fn empty_item(&mut self) -> Item {
-        Item {
-            name: None,
-            name_macro: None,
-            dir: None,
-            relation: None,
-            rel_macro: None,
-            level: 0,
-            index: 0,
-            root: 0,
-            sub_list: Vec::new(),
-        }
+        Item::default()
     }
 
-    pub fn parse_item(&mut self, linec: &LineContext) {
-        let line = &linec.seg;
-        for rs in line {
-            if rs.len() == 0 {
-                continue;
-            }
-            let new_s = rs.clone();
-            if new_s.starts_with("//") {
-                continue;
-            }
+    pub fn parse_item(&mut self, linec: &LineContext, diag: &mut Diagnostics) -> Option<Node> {
+        let tokens: Vec<Token> = linec
+            .tokens
+            .iter()
+            .filter(|t| !matches!(t.kind, TokenKind::Comment(_)))
+            .cloned()
+            .collect();
 
-            let ptc = self.extract(&new_s).unwrap();
-            for item in ptc {
+        if tokens.iter().all(|t| t.is_trivia()) {
+            return None;
+        }
+
+        match self.extract(linec, &tokens, diag) {
+            Ok(Some(node)) => {
+                let mut item = Content::item_from_node(&node);
+                let mut errors = Vec::new();
+                if let Some(attribute) = &node.attribute {
+                    item.attrs = Attrs::parse(attribute, &mut errors);
+                }
+                if let Some(attribute) = node.relation.as_ref().and_then(|r| r.attribute.as_ref()) {
+                    item.rel_attrs = Attrs::parse(attribute, &mut errors);
+                }
+                for d in errors {
+                    diag.push(d);
+                }
                 self.data_list.borrow_mut().push(item);
+                Some(node)
+            }
+            Ok(None) => None,
+            Err(e) => {
+                if e.is_error() {
+                    let level = match tokens.first().map(|t| &t.kind) {
+                        Some(TokenKind::Indent(text)) => self.indent.rough_level(text),
+                        _ => 0,
+                    };
+                    self.dropped.push((linec.span(), level));
+                }
+                diag.push(e);
+                None
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::{LexMode, Lexer};
+
+    fn parse(line: &str) -> Node {
+        let tokens = Lexer::new(LexMode::Content).tokenize_line(line, 1, 0);
+        let linec = LineContext {
+            line_number: 1,
+            offset: 0,
+            text: line.to_string(),
+            tokens: tokens.clone(),
+            lossy: false,
+        };
+        let mut diag = Diagnostics::new();
+        let node = Content::init()
+            .parse_plain(&linec, &tokens, &mut diag)
+            .unwrap()
+            .unwrap();
+        assert!(diag.list.is_empty());
+
+        node
+    }
+
+    fn relation(line: &str) -> (String, RelationOp, String) {
+        let node = parse(line);
+        let rel = node.relation.unwrap();
+
+        (node.name, rel.op, rel.target)
+    }
+
+    #[test]
+    fn hyphenated_name() {
+        let node = parse("front-end");
+        assert_eq!(node.name, "front-end");
+        assert!(node.relation.is_none());
+    }
+
+    #[test]
+    fn name_with_pluses() {
+        let node = parse("C++ + runtime");
+        assert_eq!(node.name, "C++");
+        assert_eq!(node.relation.unwrap().target, "runtime");
+    }
+
+    #[test]
+    fn quoted_name() {
+        let node = parse("\"a - b\"");
+        assert_eq!(node.name, "a - b");
+        assert!(node.relation.is_none());
+    }
+
+    #[test]
+    fn escaped_names() {
+        assert_eq!(parse("\\-").name, "-");
+        assert_eq!(parse("a\\ b").name, "a b");
+        assert_eq!(
+            relation("a\\ b - \\-"),
+            ("a b".to_string(), RelationOp::Minus, "-".to_string())
+        );
+    }
+
+    #[test]
+    fn escaped_brackets_are_not_attributes() {
+        assert_eq!(
+            relation("a\\[ + b"),
+            ("a[".to_string(), RelationOp::Plus, "b".to_string())
+        );
+        assert_eq!(
+            relation("a\\] + b"),
+            ("a]".to_string(), RelationOp::Plus, "b".to_string())
+        );
+    }
+
+    #[test]
+    fn standalone_operators() {
+        assert_eq!(
+            relation("api + db"),
+            ("api".to_string(), RelationOp::Plus, "db".to_string())
+        );
+        assert_eq!(
+            relation("front-end - back-end"),
+            (
+                "front-end".to_string(),
+                RelationOp::Minus,
+                "back-end".to_string()
+            )
+        );
+        assert_eq!(
+            relation("a * b"),
+            ("a".to_string(), RelationOp::Star, "b".to_string())
+        );
+    }
+
+    #[test]
+    fn minus_inside_attributes() {
+        let node = parse("x [tags=(a - b)]");
+        assert_eq!(node.name, "x");
+        assert_eq!(node.attribute.unwrap().text, "[tags=(a - b)]");
+        assert!(node.relation.is_none());
+
+        let node = parse("x [tags=(a - b)] + y");
+        assert_eq!(node.attribute.unwrap().text, "[tags=(a - b)]");
+        assert_eq!(node.relation.unwrap().target, "y");
+    }
+
+    fn compile(source: &str) -> crate::engine::engine::Compiled {
+        use crate::engine::engine::Engine;
+        Engine::init().compile_str(Engine::init_config("test.neml".to_string()), source)
+    }
+
+    fn codes(out: &crate::engine::engine::Compiled) -> Vec<Code> {
+        out.diagnostics.iter().map(|d| d.code).collect()
+    }
+
+    #[test]
+    fn child_of_a_dropped_line_is_an_orphan() {
+        let out = compile("#[content]\na\n\"bad\n  child\n");
+        assert_eq!(
+            codes(&out),
+            vec![Code::UnterminatedString, Code::OrphanNode]
+        );
+        let orphan = &out.diagnostics[1];
+        assert_eq!(orphan.span.line, 4);
+        assert!(orphan
+            .labels
+            .iter()
+            .any(|l| l.span.line == 3 && l.message == "its parent line failed to parse"));
+    }
+
+    #[test]
+    fn grandchild_of_a_dropped_line_is_an_orphan() {
+        let out = compile("#[content]\na\n  b\n\"bad\n    c\n");
+        assert_eq!(
+            codes(&out),
+            vec![Code::UnterminatedString, Code::OrphanNode]
+        );
+        assert_eq!(out.diagnostics[1].span.line, 5);
+    }
+
+    #[test]
+    fn children_of_an_orphan_are_not_orphans() {
+        let out = compile("#[content]\na\n\"bad\n  b\n    c\n");
+        assert_eq!(
+            codes(&out),
+            vec![Code::UnterminatedString, Code::OrphanNode]
+        );
+        assert_eq!(out.diagnostics[1].span.line, 4);
+    }
+
+    #[test]
+    fn sibling_of_a_dropped_line_keeps_its_parent() {
+        let out = compile("#[content]\na\n  \"bad\n  b\n");
+        assert_eq!(codes(&out), vec![Code::UnterminatedString]);
+        assert_eq!(out.items[2].path, "a/b");
+    }
+
+    #[test]
+    fn a_later_parent_closes_the_dropped_line() {
+        let out = compile("#[content]\n\"bad\nc\n  d\n");
+        assert_eq!(codes(&out), vec![Code::UnterminatedString]);
+        assert_eq!(out.items[2].path, "c/d");
+    }
+
+    #[test]
+    fn a_second_relation_on_one_line() {
+        let out = compile("#[content]\na + b + c\nb\nc\n");
+        assert_eq!(codes(&out), vec![Code::ChainedRelation]);
+        assert_eq!(out.edges.len(), 1);
+        assert_eq!(out.edges[0].target, "b");
+        assert_eq!(out.edges[0].rel_macro, None);
+    }
+
+    #[test]
+    fn a_relation_inside_the_target_attributes() {
+        let out = compile("#[content]\na + b [tags=(x - y)]\nb\n");
+        assert!(codes(&out).is_empty());
+        assert_eq!(out.edges.len(), 1);
+    }
+}
@@ -0,0 +1,88 @@
+use super::attr::Attrs;
+use super::content::Item;
+use super::resolve::Target;
+use crate::parse::{RelationOp, Span};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeKind {
+    Depends,
+    Conflicts,
+    Relates,
+}
+
+/*
+ * A relation of #[content] compiled into a link between two nodes:
+ *
+ *   api + db "reads from" [weight=2]
+ *
+ * is an edge of kind `Depends` from `api` to `db`, labelled
+ * `reads from`, with `[weight=2]` as its macro and `attrs`. `source`
+ * indexes the items of the document the relation was written in,
+ * `node` is what `target` resolved to once imports are loaded. Edges
+ * are laid over the node tree and may cross it in any direction.
+ */
+#[derive(Debug, Clone)]
+pub struct Edge {
+    pub kind: EdgeKind,
+    pub source: usize,
+    pub target: String,
+    pub target_span: Span,
+    pub node: Option<Target>,
+    pub label: Option<String>,
+    pub rel_macro: Option<String>,
+    pub attrs: Attrs,
+    pub span: Span,
+}
+
+impl EdgeKind {
+    pub fn from_op(op: RelationOp) -> Self {
+        match op {
+            RelationOp::Plus => EdgeKind::Depends,
+            RelationOp::Minus => EdgeKind::Conflicts,
+            RelationOp::Star => EdgeKind::Relates,
+        }
+    }
+
+    pub fn from_dir(dir: &str) -> Option<Self> {
+        match dir {
+            "+" => Some(EdgeKind::Depends),
+            "-" => Some(EdgeKind::Conflicts),
+            "*" => Some(EdgeKind::Relates),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EdgeKind::Depends => "+",
+            EdgeKind::Conflicts => "-",
+            EdgeKind::Relates => "*",
+        }
+    }
+
+    pub fn describe(&self) -> &'static str {
+        match self {
+            EdgeKind::Depends => "depends on",
+            EdgeKind::Conflicts => "conflicts with",
+            EdgeKind::Relates => "relates to",
+        }
+    }
+}
+
+impl Edge {
+    pub fn from_item(item: &Item) -> Option<Self> {
+        let kind = EdgeKind::from_dir(item.dir.as_deref()?)?;
+
+        Some(Edge {
+            kind,
+            source: item.index,
+            target: item.relation.clone()?,
+            target_span: item.target_span,
+            node: None,
+            label: item.label.clone(),
+            rel_macro: item.rel_macro.clone(),
+            attrs: item.rel_attrs.clone(),
+            span: item.rel_span,
+        })
+    }
+}
@@ -1,3 +1,4 @@
+use super::attr::Attrs;
 use poirot::raster::Lattice;
 use std::cell::RefCell;
 use std::collections::HashMap;
@@ -7,6 +8,7 @@ use std::rc::Rc;
 pub struct NeiEndpoint {
     pub index: usize,
     pub name: Option<String>,
+    pub attrs: Attrs,
     pub neighbor: Option<Rc<RefCell<Vec<NeiEndpoint>>>>,
 }
 
@@ -15,6 +17,7 @@ impl NeiEndpoint {
         NeiEndpoint {
             index: index,
             name: name,
+            attrs: Attrs::default(),
             neighbor: None,
         }
     }
@@ -1,11 +1,25 @@
-use super::content::Content;
+use super::content::{Content, Item};
+use super::edge::Edge;
 use super::endpoint::NeiEndpoint;
+use super::import::Dependency;
 use super::import::Import;
-use crate::parse::parse;
+use super::indent::{IndentStyle, Indentation};
+use super::manifest::{Manifest, PathDep};
+use super::modpath::ModulePath;
+use super::module::{ImportEdge, ImportGraph, Lookup, Module, ModuleCache, Symbol};
+use super::resolve::Resolver;
+use crate::diag::{Code, Diagnostic, Diagnostics, Renderer, SourceFile, SourceMap};
 use crate::parse::reader::BufReader;
+use crate::parse::{
+    ContentSection, Document, ImportSection, LexMode, Lexer, Span, Token, TokenKind, UseDecl,
+};
 use crate::utils::Strip;
 use poirot::raster::ComCanvas;
+use std::cell::RefCell;
 use std::env;
+use std::io::{BufRead, IsTerminal};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 
 #[derive(Debug, PartialEq)]
 pub enum SegmentType {
@@ -19,99 +33,1075 @@ pub struct Engine {
     pub seg_status: SegmentType,
     pub import_data: Import,
     pub content_data: Content,
+    pub document: Document,
+    pub diagnostics: Diagnostics,
+    pub sources: SourceMap,
+    pub cache: Rc<RefCell<ModuleCache>>,
+    pub symbols: Vec<Symbol>,
+    pub deps: Vec<usize>,
+    pub visited: Vec<usize>,
+    pub loading: Vec<PathBuf>,
+    pub graph: ImportGraph,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Config {
     pub name: String,
+    pub indent: IndentStyle,
+    pub strict: bool,
+    pub lenient: bool,
+    pub roots: Vec<PathBuf>,
+    pub entries: Vec<String>,
+    pub deps: Vec<PathDep>,
+    pub output: Option<String>,
+    pub manifest: Option<Manifest>,
+}
+
+#[derive(Debug)]
+pub struct Build {
+    pub units: Vec<Compiled>,
+    pub diagnostics: Vec<Diagnostic>,
+    pub sources: SourceMap,
+}
+
+#[derive(Debug)]
+pub struct Compiled {
+    pub arch: NeiEndpoint,
+    pub items: Vec<Item>,
+    pub edges: Vec<Edge>,
+    pub diagnostics: Vec<Diagnostic>,
+    pub sources: SourceMap,
+    pub cache: Rc<RefCell<ModuleCache>>,
+    pub output: Option<String>,
+}
+
+impl Build {
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics.iter().any(|d| d.is_error()) || self.units.iter().any(|u| u.has_errors())
+    }
+
+    pub fn render(&self, renderer: &Renderer) -> String {
+        let mut out = renderer.render_all(&self.diagnostics, &self.sources);
+        for unit in &self.units {
+            out.push_str(&unit.render(renderer));
+        }
+
+        out
+    }
+}
+
+impl Compiled {
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics.iter().any(|d| d.is_error())
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics.iter().filter(|d| d.is_error())
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics.iter().filter(|d| !d.is_error())
+    }
+
+    /*
+     * Nodes are numbered as in `arch` and `Edge::source`; 0 is the
+     * unnamed root above the top-level nodes.
+     */
+    pub fn node(&self, index: usize) -> Option<&Item> {
+        self.items.get(index).filter(|_| index != 0)
+    }
+
+    pub fn find_path(&self, path: &str) -> Option<usize> {
+        let path = path.trim_matches('/');
+        (1..self.items.len()).find(|&i| self.items[i].path == path)
+    }
+
+    pub fn find_id(&self, id: &str) -> Option<usize> {
+        (1..self.items.len()).find(|&i| self.items[i].id.as_deref() == Some(id))
+    }
+
+    /*
+     * Every node named `name` anywhere below `ancestor`, outermost
+     * first; more than one match means the name alone is ambiguous.
+     */
+    pub fn find_under(&self, ancestor: usize, name: &str) -> Vec<usize> {
+        let mut found: Vec<usize> = (ancestor + 1..self.items.len())
+            .filter(|&i| self.items[i].name.as_deref() == Some(name))
+            .filter(|&i| ancestor == 0 || self.ancestors(i).any(|a| a == ancestor))
+            .collect();
+        found.sort_by_key(|&i| self.ancestors(i).count());
+
+        found
+    }
+
+    pub fn children(&self, index: usize) -> impl Iterator<Item = usize> + '_ {
+        self.items
+            .get(index)
+            .into_iter()
+            .flat_map(|item| item.sub_list.iter().copied())
+    }
+
+    /*
+     * From the parent of `index` up to its top-level node.
+     */
+    pub fn ancestors(&self, index: usize) -> impl Iterator<Item = usize> + '_ {
+        std::iter::successors(self.items.get(index).map(|item| item.root), |&i| {
+            self.items.get(i).map(|item| item.root)
+        })
+        .take_while(|&i| i != 0)
+    }
+
+    pub fn span(&self, index: usize) -> Option<Span> {
+        self.node(index).map(|item| item.span)
+    }
+
+    /*
+     * The edges leaving the node `arch` numbers `index`.
+     */
+    pub fn edges_from(&self, index: usize) -> impl Iterator<Item = &Edge> {
+        self.edges.iter().filter(move |e| e.source == index)
+    }
+
+    /*
+     * The node a `Target::Import` points at, with the path of the
+     * module it is defined in.
+     */
+    pub fn imported(&self, module: usize, index: usize) -> Option<(PathBuf, Item)> {
+        let cache = self.cache.borrow();
+        let m = cache.modules.get(module)?;
+        let item = m.items.get(index).filter(|_| index != 0)?;
+
+        Some((m.path.clone(), item.clone()))
+    }
+
+    pub fn render(&self, renderer: &Renderer) -> String {
+        renderer.render_all(&self.diagnostics, &self.sources)
+    }
 }
 
 pub struct LineContext {
     pub line_number: usize,
-    pub seg: Vec<String>,
+    pub offset: usize,
+    pub text: String,
+    pub tokens: Vec<Token>,
+    pub lossy: bool,
+}
+
+impl LineContext {
+    pub fn slice(&self, span: Span) -> &str {
+        let start = span.start.saturating_sub(self.offset);
+        let end = span.end.saturating_sub(self.offset);
+        self.text.get(start..end).unwrap_or("")
+    }
+
+    pub fn span(&self) -> Span {
+        Span::new(
+            self.offset,
+            self.offset + self.text.len(),
+            self.line_number,
+            1,
+        )
+    }
+}
+
+impl Config {
+    pub fn apply_manifest(&mut self, manifest: Manifest) {
+        self.entries = manifest
+            .entries
+            .iter()
+            .map(|e| e.to_string_lossy().into_owned())
+            .collect();
+        if let Some(first) = self.entries.first() {
+            self.name = first.clone();
+        }
+        self.roots = manifest.include.clone();
+        self.deps = manifest.deps.clone();
+        if let Some(indent) = manifest.indent {
+            self.indent = indent;
+        }
+        self.strict = manifest.strict;
+        self.lenient = manifest.lenient;
+        self.manifest = Some(manifest);
+    }
+
+    /*
+     * One config per document to compile: each project entry with its
+     * output target, or this config alone.
+     */
+    pub fn units(&self) -> Vec<Config> {
+        if self.entries.is_empty() {
+            return vec![self.clone()];
+        }
+
+        self.entries
+            .iter()
+            .map(|entry| Config {
+                name: entry.clone(),
+                output: self
+                    .manifest
+                    .as_ref()
+                    .and_then(|m| m.target(Path::new(entry)))
+                    .map(|p| p.to_string_lossy().into_owned())
+                    .or_else(|| self.output.clone()),
+                ..self.clone()
+            })
+            .collect()
+    }
 }
 
 impl Engine {
+    /*
+     * `line` is a document, or a project: a neml.toml or the directory
+     * holding one. A project's entries are compiled by `build`.
+     */
     pub fn init_config(line: String) -> Config {
-        Config { name: line.clone() }
+        let mut config = Config {
+            name: line.clone(),
+            indent: IndentStyle::Spaces(2),
+            strict: false,
+            lenient: false,
+            roots: Vec::new(),
+            entries: Vec::new(),
+            deps: Vec::new(),
+            output: None,
+            manifest: None,
+        };
+
+        if let Some(file) = Manifest::find(Path::new(&line)) {
+            let manifest = Manifest::load(&file).unwrap_or_else(|e| Manifest::unreadable(&file, e));
+            config.apply_manifest(manifest);
+        }
+
+        config
     }
 
     pub fn init() -> Self {
+        Engine::init_cached(Rc::new(RefCell::new(ModuleCache::new())))
+    }
+
+    /*
+     * Engines sharing `cache` compile each imported module once, until
+     * its file or one of its imports changes.
+     */
+    pub fn init_cached(cache: Rc<RefCell<ModuleCache>>) -> Self {
         Engine {
             seg_status: SegmentType::SegInit,
             import_data: Import::init(),
             content_data: Content::init(),
+            document: Document::default(),
+            diagnostics: Diagnostics::new(),
+            sources: SourceMap::new(),
+            cache,
+            symbols: Vec::new(),
+            deps: Vec::new(),
+            visited: Vec::new(),
+            loading: Vec::new(),
+            graph: ImportGraph::new(),
         }
     }
 
-    pub fn compile(&mut self, config_data: Config) -> std::io::Result<NeiEndpoint> {
+    pub fn read_lines<R: BufRead>(mut reader: BufReader<R>) -> std::io::Result<Vec<LineContext>> {
         let mut raw_data = Vec::new();
-        let mut line_number: usize = 1;
-        for line in BufReader::open(config_data.name)? {
+        let mut offset: usize = 0;
+        while let Some(line) = reader.next() {
             let datat = line?.to_string();
-            let data = Strip::rstrip(datat, '\n');
-            let line = parse::parse_simple(data);
+            let len = datat.len();
+            let data = Strip::rstrip(Strip::rstrip(datat, '\n'), '\r');
 
             let context = LineContext {
-                line_number: line_number,
-                seg: line,
+                line_number: raw_data.len() + 1,
+                offset,
+                text: data,
+                tokens: Vec::new(),
+                lossy: reader.lossy,
             };
 
             raw_data.push(context);
 
-            line_number += 1;
+            offset += len;
+        }
+
+        Ok(raw_data)
+    }
+
+    pub fn parse_source(&mut self, config_data: &Config, raw_data: Vec<LineContext>) {
+        if let Some(last) = raw_data.last() {
+            self.document.span = Span::new(0, last.offset + last.text.len(), 1, 1);
+        }
+
+        let mut source = SourceFile::new(&config_data.name);
+        for s in &raw_data {
+            source.push_line(s.offset, &s.text);
         }
+        self.sources.add(source);
+        self.content_data.indent = Indentation::new(config_data.indent, config_data.strict);
+        self.content_data.lenient = config_data.lenient;
 
-        for s in raw_data {
-            self.parse_line(&s);
+        for mut s in raw_data {
+            self.parse_line(&mut s);
         }
+        self.finish_import();
 
-        self.content_data.parse_level();
+        self.content_data.parse_level(&mut self.diagnostics);
         self.content_data.parse_sub_arch();
+        self.content_data.parse_paths(&mut self.diagnostics);
+        self.content_data.parse_edges();
 
-        let arch = self.content_data.parse_build_arch();
+        self.load_imports(config_data);
+        self.resolve_names();
+        self.lint_imports();
 
-        Ok(arch)
+        self.diagnostics.set_file(&config_data.name);
     }
 
-    pub fn start(&mut self, config_data: Config) -> std::io::Result<()> {
-        let mut raw_data = Vec::new();
-        let mut line_number: usize = 1;
-        for line in BufReader::open(config_data.name)? {
-            let datat = line?.to_string();
-            let data = Strip::rstrip(datat, '\n');
-            let line = parse::parse_simple(data);
+    /*
+     * Every `use` path names a `.neml` file, see `ModulePath` for where
+     * it is looked up. Each file is compiled once into the module cache,
+     * and each imported anchor becomes a `Symbol` visible to this
+     * document's content.
+     */
+    pub fn load_imports(&mut self, config_data: &Config) {
+        let file = Path::new(&config_data.name);
+        let base = file.parent().map(Path::to_path_buf).unwrap_or_default();
+        self.loading
+            .push(file.canonicalize().unwrap_or_else(|_| file.to_path_buf()));
 
-            let context = LineContext {
-                line_number: line_number,
-                seg: line,
+        let mut deps = std::mem::take(&mut self.import_data.data_list);
+        let mut failed: Vec<(String, Span)> = Vec::new();
+        for dep in deps.iter_mut() {
+            let (path, anchor) = match (&dep.path, &dep.anchor) {
+                (Some(p), Some(a)) => (p, a),
+                _ => continue,
             };
+            if failed.contains(&(path.clone(), dep.decl)) {
+                continue;
+            }
 
-            raw_data.push(context);
+            let module = match self.load_module(config_data, &base, path, dep.path_span) {
+                Some(m) => m,
+                None => {
+                    failed.push((path.clone(), dep.decl));
+                    continue;
+                }
+            };
+            dep.loaded = true;
+            if !self.deps.contains(&module) {
+                self.deps.push(module);
+            }
+
+            if dep.glob {
+                self.import_glob(dep, module, anchor);
+                continue;
+            }
+
+            let lookup = Module::lookup(&self.cache.borrow().modules, module, anchor);
+            let (module, index) = match lookup {
+                Lookup::Found { module, index } => (module, index),
+                Lookup::Private => {
+                    self.diagnostics.push(
+                        Diagnostic::error(
+                            Code::PrivateAnchor,
+                            format!("anchor `{}` in module `{}` is private", anchor, path),
+                            dep.span,
+                        )
+                        .with_help(format!(
+                            "mark it `pub` in `{}`, or re-export it with `pub use`",
+                            path
+                        )),
+                    );
+                    continue;
+                }
+                Lookup::Missing => {
+                    self.diagnostics.push(Diagnostic::error(
+                        Code::UnknownAnchor,
+                        format!("no anchor `{}` in module `{}`", anchor, path),
+                        dep.span,
+                    ));
+                    continue;
+                }
+            };
+
+            self.symbols.push(Symbol {
+                name: dep.name().unwrap_or_default(),
+                module,
+                index,
+                glob: false,
+                public: dep.public,
+                span: dep.span,
+            });
+        }
+        self.merge_globs();
+        self.import_data.data_list = deps;
+    }
 
-            line_number += 1;
+    /*
+     * `m.*` brings in the `pub` top-level nodes of `m` and everything it
+     * re-exports; `m.a.*` the `pub` children of `a`.
+     */
+    pub fn import_glob(&mut self, dep: &Dependency, module: usize, anchor: &str) {
+        let path = dep.path.as_deref().unwrap_or("");
+        let (module, index) = if anchor.is_empty() {
+            (module, 0)
+        } else {
+            let cache = self.cache.borrow();
+            match cache.modules[module].find(anchor) {
+                Some(index) => (module, index),
+                None => match Module::lookup(&cache.modules, module, anchor) {
+                    Lookup::Found { module, index } => (module, index),
+                    _ => {
+                        self.diagnostics.push(Diagnostic::error(
+                            Code::UnknownAnchor,
+                            format!("no anchor `{}` in module `{}`", anchor, path),
+                            dep.span,
+                        ));
+                        return;
+                    }
+                },
+            }
+        };
+
+        let cache = self.cache.borrow();
+        let m = &cache.modules[module];
+        let mut found: Vec<(String, usize, usize)> = m
+            .items
+            .iter()
+            .enumerate()
+            .skip(1)
+            .filter(|(_, item)| item.root == index && item.public)
+            .map(|(i, item)| (item.name.clone().unwrap_or_default(), module, i))
+            .collect();
+        if index == 0 {
+            found.extend(
+                m.symbols
+                    .iter()
+                    .filter(|s| s.public)
+                    .map(|s| (s.name.clone(), s.module, s.index)),
+            );
         }
 
-        for s in raw_data {
-            self.parse_line(&s);
+        for (name, module, index) in found {
+            self.symbols.push(Symbol {
+                name,
+                module,
+                index,
+                glob: true,
+                public: dep.public,
+                span: dep.span,
+            });
         }
+    }
 
-        self.content_data.parse_level();
-        self.content_data.parse_sub_arch();
+    /*
+     * An explicit import hides every glob import of the same name. Two
+     * globs bringing in different nodes under one name only warn here;
+     * using that name is then ambiguous.
+     */
+    pub fn merge_globs(&mut self) {
+        let explicit: Vec<String> = self
+            .symbols
+            .iter()
+            .filter(|s| !s.glob)
+            .map(|s| s.name.clone())
+            .collect();
+
+        let mut kept: Vec<Symbol> = Vec::new();
+        let mut warned: Vec<String> = Vec::new();
+        for sym in std::mem::take(&mut self.symbols) {
+            if sym.glob && explicit.contains(&sym.name) {
+                continue;
+            }
 
-        self.generate_object_tree();
+            if sym.glob {
+                if let Some(other) = kept.iter().find(|k| k.glob && k.name == sym.name) {
+                    if other.module == sym.module && other.index == sym.index {
+                        continue;
+                    }
+                    if !warned.contains(&sym.name) {
+                        self.diagnostics.push(
+                            Diagnostic::warning(
+                                Code::GlobConflict,
+                                format!(
+                                    "`{}` is brought in by more than one glob import",
+                                    sym.name
+                                ),
+                                sym.span,
+                            )
+                            .with_label(other.span, "also imported here")
+                            .with_help(format!("import `{}` explicitly to pick one", sym.name)),
+                        );
+                        warned.push(sym.name.clone());
+                    }
+                }
+            }
+
+            kept.push(sym);
+        }
+
+        self.symbols = kept;
+    }
+
+    pub fn load_module(
+        &mut self,
+        config_data: &Config,
+        base: &Path,
+        path: &str,
+        span: Span,
+    ) -> Option<usize> {
+        let module_path = match ModulePath::parse(path, span) {
+            Ok(p) => p,
+            Err(d) => {
+                self.diagnostics.push(d);
+                return None;
+            }
+        };
+
+        let importer = self.loading.last().cloned().unwrap_or_default();
+        let root = self.project_root(config_data, &importer);
+        let mut tried: Vec<PathBuf> = Vec::new();
+        match &module_path {
+            ModulePath::Relative(_) => {
+                let dir = importer.parent().unwrap_or(base);
+                let file = ModulePath::normalize(&dir.join(module_path.file()));
+                if !file.starts_with(&root) {
+                    self.diagnostics.push(
+                        Diagnostic::error(
+                            Code::PathEscapesRoot,
+                            format!("`{}` leads outside the project root", path),
+                            span,
+                        )
+                        .with_note(format!("it names `{}`", file.display()))
+                        .with_note(format!("the project root is `{}`", root.display()))
+                        .with_help(
+                            "add its directory to the include roots and import it by library path",
+                        ),
+                    );
+                    return None;
+                }
+                tried.push(file);
+            }
+            ModulePath::Root(_) => tried.push(root.join(module_path.file())),
+            ModulePath::Library(segments) => {
+                if segments.len() > 1 {
+                    let rest = ModulePath::Library(segments[1..].to_vec()).file();
+                    for dep in config_data.deps.iter().filter(|d| d.name == segments[0]) {
+                        tried.push(dep.path.join(&rest));
+                    }
+                }
+                tried.extend(
+                    Engine::search_roots(config_data, &root)
+                        .iter()
+                        .map(|root| root.join(module_path.file())),
+                );
+            }
+        }
+
+        let file = match tried.iter().find(|f| f.is_file()) {
+            Some(file) => file.clone(),
+            None => {
+                let mut d = Diagnostic::error(
+                    Code::UnresolvedImport,
+                    format!("cannot find module `{}`", path),
+                    span,
+                );
+                for f in &tried {
+                    d = d.with_note(format!("looked for `{}`", f.display()));
+                }
+                self.diagnostics.push(
+                    d.with_help("add the directory holding it to the include roots or NEML_PATH"),
+                );
+                return None;
+            }
+        };
+
+        let key = file.canonicalize().unwrap_or_else(|_| file.clone());
+        self.graph.add(ImportEdge {
+            from: self.loading.last().cloned().unwrap_or_default(),
+            to: key.clone(),
+            file: config_data.name.clone(),
+            line: self
+                .sources
+                .get(&config_data.name)
+                .and_then(|f| f.line(span.line))
+                .unwrap_or("")
+                .trim()
+                .to_string(),
+            span,
+        });
+
+        if let Some(cycle) = self.graph.cycle(&self.loading, &key) {
+            let d = Engine::cycle_error(&cycle, span);
+            self.diagnostics.push(d);
+            return None;
+        }
+
+        let cached = self.cache.borrow().position(&key);
+        if let Some(index) = cached {
+            if self.visited.contains(&index) {
+                return Some(index);
+            }
+            if self.cache.borrow().is_fresh(index, &mut Vec::new()) {
+                self.replay(index);
+                return Some(index);
+            }
+        }
+
+        let bytes = match std::fs::read(&file) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                self.diagnostics.push(
+                    Diagnostic::error(
+                        Code::UnresolvedImport,
+                        format!("cannot read module `{}`", path),
+                        span,
+                    )
+                    .with_note(e.to_string()),
+                );
+                return None;
+            }
+        };
+        let raw_data = Engine::read_lines(BufReader::new(bytes.as_slice()))
+            .expect("reading from memory cannot fail");
+
+        let config = Config {
+            name: file.to_string_lossy().into_owned(),
+            ..config_data.clone()
+        };
+
+        let mut child = Engine::init_cached(self.cache.clone());
+        child.loading = self.loading.clone();
+        child.visited = std::mem::take(&mut self.visited);
+        child.graph = std::mem::take(&mut self.graph);
+        child.parse_source(&config, raw_data);
+
+        self.visited = std::mem::take(&mut child.visited);
+        self.graph = std::mem::take(&mut child.graph);
+
+        let (own, imported): (Vec<Diagnostic>, Vec<Diagnostic>) = child
+            .diagnostics
+            .take()
+            .into_iter()
+            .partition(|d| d.file.as_deref() == Some(config.name.as_str()));
+        let source = child.sources.get(&config.name).cloned();
+        self.diagnostics.list.extend(imported);
+        self.diagnostics.list.extend(own.iter().cloned());
+        self.sources.extend(std::mem::take(&mut child.sources));
+
+        let items = std::mem::take(&mut *child.content_data.data_list.borrow_mut());
+        let index = self.cache.borrow_mut().insert(Module {
+            name: module_path.to_string(),
+            path: key,
+            hash: ModuleCache::hash(&bytes),
+            document: std::mem::take(&mut child.document),
+            items,
+            edges: std::mem::take(&mut child.content_data.edges),
+            symbols: child.symbols,
+            deps: child.deps,
+            diagnostics: own,
+            source,
+        });
+        self.visited.push(index);
+
+        Some(index)
+    }
+
+    /*
+     * A cache hit still reports the module's diagnostics, and those of
+     * its imports, once per compile.
+     */
+    pub fn replay(&mut self, index: usize) {
+        if self.visited.contains(&index) {
+            return;
+        }
+        self.visited.push(index);
+
+        let deps = {
+            let cache = self.cache.borrow();
+            let m = &cache.modules[index];
+            self.diagnostics.list.extend(m.diagnostics.iter().cloned());
+            if let Some(source) = &m.source {
+                self.sources.add(source.clone());
+            }
+            m.deps.clone()
+        };
+
+        for dep in deps {
+            self.replay(dep);
+        }
+    }
+
+    /*
+     *  error[E0107]: import cycle: a.neml -> b.neml -> a.neml
+     *    = note: a.neml:1: use b.x
+     *    = note: b.neml:1: use a.y
+     */
+    pub fn cycle_error(cycle: &[&ImportEdge], span: Span) -> Diagnostic {
+        let mut files: Vec<String> = cycle.iter().map(|e| e.file.clone()).collect();
+        if let Some(first) = files.first() {
+            files.push(first.clone());
+        }
+
+        let mut d = Diagnostic::error(
+            Code::ImportCycle,
+            format!("import cycle: {}", files.join(" -> ")),
+            span,
+        );
+        for e in cycle {
+            d = d.with_note(format!("{}:{}: {}", e.file, e.span.line, e.line));
+        }
+
+        d.with_help("move the shared nodes into a module outside the cycle")
+    }
+
+    /*
+     * The root a `/` path starts from and a `./` or `../` path may not
+     * leave: the innermost of the manifest root, the include roots, the
+     * dependencies and NEML_PATH that holds `file`, or else the
+     * directory of the document the build started from.
+     */
+    pub fn project_root(&self, config_data: &Config, file: &Path) -> PathBuf {
+        let mut roots: Vec<PathBuf> = config_data
+            .manifest
+            .iter()
+            .map(|m| m.root.clone())
+            .collect();
+        roots.extend(config_data.roots.iter().cloned());
+        roots.extend(config_data.deps.iter().map(|d| d.path.clone()));
+        if let Some(paths) = env::var_os("NEML_PATH") {
+            roots.extend(env::split_paths(&paths));
+        }
+
+        let inner = roots
+            .iter()
+            .filter_map(|r| r.canonicalize().ok())
+            .filter(|r| file.starts_with(r))
+            .max_by_key(|r| r.components().count());
+
+        match inner {
+            Some(root) => root,
+            None => self
+                .loading
+                .first()
+                .and_then(|f| f.parent())
+                .map(Path::to_path_buf)
+                .unwrap_or_default(),
+        }
+    }
+
+    /*
+     * Search order for library paths: `root`, the project root of the
+     * importing file, then the manifest root, `Config::roots` in the
+     * order given, and the entries of `NEML_PATH`. The importing file's
+     * own directory is not searched, that is what `./` is for. Relative
+     * roots are taken from the working directory.
+     */
+    pub fn search_roots(config_data: &Config, root: &Path) -> Vec<PathBuf> {
+        let mut roots = vec![root.to_path_buf()];
+        if let Some(manifest) = &config_data.manifest {
+            roots.push(manifest.root.clone());
+        }
+        roots.extend(config_data.roots.iter().cloned());
+        if let Some(paths) = env::var_os("NEML_PATH") {
+            roots.extend(env::split_paths(&paths).filter(|p| !p.as_os_str().is_empty()));
+        }
+
+        let mut seen = Vec::new();
+        roots.retain(|r| {
+            if seen.contains(r) {
+                return false;
+            }
+            seen.push(r.clone());
+            true
+        });
+
+        roots
+    }
+
+    /*
+     * Binds the target of every edge to a local node or an imported
+     * anchor, see `Resolver::resolve`.
+     */
+    pub fn resolve_names(&mut self) {
+        let items = self.content_data.data_list.borrow();
+        let cache = self.cache.borrow();
+        let resolver = Resolver::new(&items, &self.symbols, &cache.modules);
+
+        /* a `use` that already failed is not reported again at each of its uses */
+        let failed: Vec<String> = self
+            .import_data
+            .data_list
+            .iter()
+            .filter(|dep| {
+                !dep.loaded
+                    || self
+                        .diagnostics
+                        .list
+                        .iter()
+                        .any(|d| d.is_error() && d.span == dep.span)
+            })
+            .filter_map(Dependency::name)
+            .collect();
+
+        for edge in self.content_data.edges.iter_mut() {
+            match resolver.resolve(&edge.target, edge.target_span, edge.source) {
+                Ok(target) => edge.node = Some(target),
+                Err(d)
+                    if d.code == Code::UnknownName
+                        && failed
+                            .iter()
+                            .any(|name| edge.target.split('/').next() == Some(name)) => {}
+                Err(d) => self.diagnostics.push(d),
+            }
+        }
+
+        for d in resolver.shadowed() {
+            self.diagnostics.push(d);
+        }
+    }
+
+    /*
+     * Two explicit imports binding one name to different nodes is an
+     * error. Importing the same node twice, or an import whose name no
+     * relation target mentions, is a warning; `pub use` counts as used.
+     */
+    pub fn lint_imports(&mut self) {
+        for (i, sym) in self.symbols.iter().enumerate() {
+            if sym.glob {
+                continue;
+            }
+
+            let first = match self.symbols[..i].iter().find(|s| {
+                !s.glob && (s.name == sym.name || (s.module == sym.module && s.index == sym.index))
+            }) {
+                Some(first) => first,
+                None => continue,
+            };
+
+            let same = first.module == sym.module && first.index == sym.index;
+            let d = if !same {
+                Diagnostic::error(
+                    Code::AliasConflict,
+                    format!("`{}` is bound by two imports", sym.name),
+                    sym.span,
+                )
+                .with_label(first.span, format!("`{}` first bound here", first.name))
+                .with_help("rename one of them with `as`")
+            } else {
+                Diagnostic::warning(
+                    Code::DuplicateImport,
+                    format!("`{}` imports an anchor that is already imported", sym.name),
+                    sym.span,
+                )
+                .with_label(first.span, "first imported here")
+                .with_help("remove the duplicate")
+            };
+            self.diagnostics.push(d);
+        }
+
+        let items = self.content_data.data_list.borrow();
+        let used: Vec<&str> = items
+            .iter()
+            .filter_map(|item| item.relation.as_deref())
+            .map(|name| name.split('/').next().unwrap_or(name))
+            .collect();
+
+        for dep in &self.import_data.data_list {
+            if dep.public || !dep.loaded {
+                continue;
+            }
+
+            let symbols: Vec<usize> = (0..self.symbols.len())
+                .filter(|&i| self.symbols[i].span == dep.span)
+                .collect();
+            if symbols
+                .iter()
+                .any(|&i| used.contains(&self.symbols[i].name.as_str()))
+            {
+                continue;
+            }
+            if self
+                .diagnostics
+                .list
+                .iter()
+                .any(|d| d.is_error() && d.span == dep.span)
+            {
+                continue;
+            }
+
+            let path = dep.path.as_deref().unwrap_or("");
+            let name = match (&dep.alias, dep.anchor.as_deref()) {
+                (_, Some("")) if dep.glob => format!("{}.*", path),
+                (_, Some(anchor)) if dep.glob => format!("{}.{}.*", path, anchor),
+                (Some(alias), _) => alias.clone(),
+                (None, Some(anchor)) => anchor.rsplit('/').next().unwrap_or(anchor).to_string(),
+                _ => continue,
+            };
+
+            self.diagnostics.push(
+                Diagnostic::warning(
+                    Code::UnusedImport,
+                    format!("unused import `{}`", name),
+                    dep.span,
+                )
+                .with_help("remove it, or refer to it from #[content]"),
+            );
+        }
+    }
+
+    pub fn symbol(&self, name: &str) -> Option<&Symbol> {
+        self.symbols.iter().find(|s| s.name == name)
+    }
+
+    pub fn compile_lines(&mut self, config_data: &Config, raw_data: Vec<LineContext>) -> Compiled {
+        self.parse_source(config_data, raw_data);
+
+        let arch = self.content_data.parse_build_arch();
+
+        Compiled {
+            arch,
+            items: self.content_data.data_list.borrow().clone(),
+            edges: self.content_data.edges.clone(),
+            diagnostics: self.diagnostics.take(),
+            sources: std::mem::take(&mut self.sources),
+            cache: self.cache.clone(),
+            output: config_data.output.clone(),
+        }
+    }
+
+    pub fn compile(&mut self, config_data: Config) -> std::io::Result<Compiled> {
+        let raw_data = Engine::read_lines(BufReader::open(&config_data.name)?)?;
+
+        Ok(self.compile_lines(&config_data, raw_data))
+    }
+
+    /*
+     * The document itself comes from `reader`; `config_data.name` labels
+     * its diagnostics and sources. Without a manifest its directory (the
+     * working directory for a bare name) is the project root imports are
+     * looked up from, and imported modules are read from disk.
+     */
+    pub fn compile_reader<R: BufRead>(
+        &mut self,
+        config_data: Config,
+        reader: R,
+    ) -> std::io::Result<Compiled> {
+        let raw_data = Engine::read_lines(BufReader::new(reader))?;
+
+        Ok(self.compile_lines(&config_data, raw_data))
+    }
+
+    pub fn compile_str(&mut self, config_data: Config, source: &str) -> Compiled {
+        let raw_data = Engine::read_lines(BufReader::new(source.as_bytes()))
+            .expect("reading from memory cannot fail");
+
+        self.compile_lines(&config_data, raw_data)
+    }
+
+    /*
+     * Compiles every entry of the project, or the single document, with
+     * one module cache.
+     */
+    pub fn build(config_data: Config) -> Build {
+        let mut build = Build {
+            units: Vec::new(),
+            diagnostics: Vec::new(),
+            sources: SourceMap::new(),
+        };
+
+        if let Some(manifest) = &config_data.manifest {
+            build
+                .diagnostics
+                .extend(manifest.diagnostics.iter().cloned());
+            build.sources.add(manifest.source.clone());
+            if manifest.has_errors() {
+                return build;
+            }
+        }
+
+        let cache = Rc::new(RefCell::new(ModuleCache::new()));
+        for entry in config_data.units() {
+            let mut engine = Engine::init_cached(cache.clone());
+            match engine.compile(entry.clone()) {
+                Ok(unit) => build.units.push(unit),
+                Err(e) => build
+                    .diagnostics
+                    .push(Engine::entry_error(&config_data, &entry.name, e)),
+            }
+        }
+
+        build
+    }
+
+    pub fn entry_error(config_data: &Config, entry: &str, e: std::io::Error) -> Diagnostic {
+        let mut d = Diagnostic::error(
+            Code::ManifestEntry,
+            format!("cannot read entry `{}`: {}", entry, e),
+            Span::new(0, 0, 1, 1),
+        );
+        if let Some(manifest) = &config_data.manifest {
+            d.file = Some(manifest.source.name.clone());
+            if let Some(i) = config_data.entries.iter().position(|x| x == entry) {
+                d.span = manifest.entry_spans.get(i).copied().unwrap_or(d.span);
+            }
+        }
+
+        d
+    }
+
+    pub fn start(&mut self, config_data: Config) -> std::io::Result<()> {
+        if config_data.manifest.is_some() {
+            return Engine::start_project(config_data);
+        }
+
+        let raw_data = Engine::read_lines(BufReader::open(&config_data.name)?)?;
+        self.parse_source(&config_data, raw_data);
+
+        let renderer = Renderer {
+            color: std::io::stderr().is_terminal(),
+        };
+        eprint!(
+            "{}",
+            renderer.render_all(&self.diagnostics.take(), &self.sources)
+        );
+
+        self.generate_object_tree(config_data.output.clone());
+
+        Ok(())
+    }
+
+    /*
+     * Compiles every entry with `build`, then draws the units that
+     * could be read.
+     */
+    pub fn start_project(config_data: Config) -> std::io::Result<()> {
+        let renderer = Renderer {
+            color: std::io::stderr().is_terminal(),
+        };
+
+        let build = Engine::build(config_data);
+        eprint!("{}", build.render(&renderer));
+
+        for unit in &build.units {
+            if let Some(dir) = unit.output.as_ref().and_then(|o| Path::new(o).parent()) {
+                std::fs::create_dir_all(dir)?;
+            }
+            Engine::draw(&unit.arch, unit.output.clone());
+        }
 
         Ok(())
     }
 
-    pub fn generate_object_tree(&mut self) {
+    pub fn generate_object_tree(&mut self, target: Option<String>) {
         let arch = self.content_data.parse_build_arch();
+        Engine::draw(&arch, target);
+    }
+
+    pub fn draw(arch: &NeiEndpoint, target: Option<String>) {
         let mut root = arch.create_object_tree();
 
-        let arg = if env::args().count() == 2 {
-            env::args().nth(1).unwrap()
-        } else {
-            panic!("Please enter a target file path")
+        let arg = match target {
+            Some(target) => target,
+            None if env::args().count() == 2 => env::args().nth(1).unwrap(),
+            None => panic!("Please enter a target file path"),
         };
 
         let mut cc = ComCanvas::new(arg, (1600, 1600), None);
@@ -130,26 +1120,58 @@ impl Engine {
         self.seg_status = status;
     }
 
-    pub fn parse_line(&mut self, linec: &LineContext) {
-        let line = &linec.seg;
+    pub fn lex_mode(&self) -> LexMode {
+        match self.seg_status {
+            SegmentType::SegImport => LexMode::Import,
+            _ => LexMode::Content,
+        }
+    }
+
+    pub fn parse_line(&mut self, linec: &mut LineContext) {
+        let lexer = Lexer::new(self.lex_mode());
+        linec.tokens = lexer.tokenize_line(&linec.text, linec.line_number, linec.offset);
 
-        if line.len() < 1 {
+        if linec.tokens.is_empty() {
             return;
         }
 
+        if linec.lossy {
+            let first = linec.tokens[0].span;
+            let last = linec.tokens[linec.tokens.len() - 1].span;
+            self.diagnostics.push(
+                Diagnostic::error(Code::InvalidUtf8, "line is not valid UTF-8", first.to(last))
+                    .with_note("invalid bytes were replaced with U+FFFD"),
+            );
+        }
+
         let mut seg_skip = false;
 
-        for s in line {
-            match s.as_str() {
-                "#[import]" => {
-                    self.seg_status_switch(SegmentType::SegImport);
-                    seg_skip = true;
-                }
-                "#[content]" => {
-                    self.seg_status_switch(SegmentType::SegContent);
-                    seg_skip = true;
+        for t in &linec.tokens {
+            if let TokenKind::Section(name) = &t.kind {
+                self.finish_import();
+                match name.as_str() {
+                    "import" => {
+                        self.seg_status_switch(SegmentType::SegImport);
+                        self.document.imports.push(ImportSection::new(t.span));
+                        seg_skip = true;
+                    }
+                    "content" => {
+                        self.seg_status_switch(SegmentType::SegContent);
+                        self.document.contents.push(ContentSection::new(t.span));
+                        seg_skip = true;
+                    }
+                    _ => {
+                        self.diagnostics.push(
+                            Diagnostic::error(
+                                Code::UnknownSection,
+                                format!("unknown section `#[{}]`", name),
+                                t.span,
+                            )
+                            .with_help("expected `#[import]` or `#[content]`"),
+                        );
+                        seg_skip = true;
+                    }
                 }
-                _ => {}
             }
         }
 
@@ -158,15 +1180,306 @@ impl Engine {
         }
     }
 
+    pub fn push_use(&mut self, decl: UseDecl) {
+        if let Some(section) = self.document.imports.last_mut() {
+            section.push(decl);
+        }
+    }
+
+    pub fn finish_import(&mut self) {
+        if let Some(decl) = self.import_data.finish(&mut self.diagnostics) {
+            self.push_use(decl);
+        }
+    }
+
     pub fn parse_raw_line(&mut self, linec: &LineContext) {
         match self.seg_status {
-            SegmentType::SegInit => {}
+            SegmentType::SegInit => {
+                if let Some(t) = linec.tokens.iter().find(|t| !t.is_trivia()) {
+                    self.diagnostics.push(
+                        Diagnostic::error(
+                            Code::OutsideSection,
+                            "line outside of any section",
+                            t.span.to(linec.tokens[linec.tokens.len() - 1].span),
+                        )
+                        .with_help("start the file with `#[import]` or `#[content]`"),
+                    );
+                }
+            }
             SegmentType::SegImport => {
-                self.import_data.parse_item(linec);
+                for decl in self.import_data.parse_item(linec, &mut self.diagnostics) {
+                    self.push_use(decl);
+                }
             }
             SegmentType::SegContent => {
-                self.content_data.parse_item(linec);
+                if let Some(node) = self.content_data.parse_item(linec, &mut self.diagnostics) {
+                    if let Some(section) = self.document.contents.last_mut() {
+                        section.push(node);
+                    }
+                }
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::resolve::Target;
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("nemlc-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn relative_import_cannot_leave_the_project_root() {
+        let dir = scratch("escape");
+        std::fs::create_dir_all(dir.join("proj")).unwrap();
+        std::fs::write(dir.join("outside.neml"), "#[content]\npub x\n").unwrap();
+        std::fs::write(dir.join("proj/sib.neml"), "#[content]\npub y\n").unwrap();
+        let main = dir.join("proj/main.neml");
+        std::fs::write(
+            &main,
+            "#[import]\nuse ../outside.x\nuse ./sib.y\n\n#[content]\na + y\n",
+        )
+        .unwrap();
+
+        let mut engine = Engine::init();
+        let config = Engine::init_config(main.to_string_lossy().into_owned());
+        let out = engine.compile(config).unwrap();
+
+        let escapes: Vec<&Diagnostic> = out
+            .diagnostics
+            .iter()
+            .filter(|d| d.code == Code::PathEscapesRoot)
+            .collect();
+        assert_eq!(escapes.len(), 1);
+        assert_eq!(escapes[0].span.line, 2);
+        assert_eq!(engine.cache.borrow().modules.len(), 1);
+        assert_eq!(engine.cache.borrow().modules[0].name, "./sib");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn imported_edge_targets_resolve_through_the_compiled_unit() {
+        let dir = scratch("imported");
+        std::fs::write(dir.join("shapes.neml"), "#[content]\npub box\n  pub lid\n").unwrap();
+        let main = dir.join("main.neml");
+        std::fs::write(
+            &main,
+            "#[import]\nuse ./shapes.box\n\n#[content]\na + box/lid\n",
+        )
+        .unwrap();
+
+        let config = Engine::init_config(main.to_string_lossy().into_owned());
+        let out = Engine::init().compile(config).unwrap();
+        assert!(!out.has_errors(), "{:?}", out.diagnostics);
+
+        let (module, index) = match out.edges[0].node {
+            Some(Target::Import { module, index }) => (module, index),
+            other => panic!("expected an imported target, found {:?}", other),
+        };
+        let (path, item) = out.imported(module, index).unwrap();
+        assert_eq!(path, dir.join("shapes.neml").canonicalize().unwrap());
+        assert_eq!(item.name.as_deref(), Some("lid"));
+        assert_eq!(item.path, "box/lid");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn compile(source: &str) -> Compiled {
+        Engine::init().compile_str(Engine::init_config("test.neml".to_string()), source)
+    }
+
+    #[test]
+    fn find_under_is_outermost_first() {
+        let out = compile("#[content]\na\n  b\n    x\n  x\nx\n");
+        assert_eq!(out.find_under(0, "x"), vec![5, 4, 3]);
+        assert_eq!(out.find_under(1, "x"), vec![4, 3]);
+        assert_eq!(out.find_under(2, "x"), vec![3]);
+    }
+
+    #[test]
+    fn slash_in_a_name_is_rejected() {
+        let out = compile("#[content]\n\"a/b\"\n  c\na\n  b\n");
+        let codes: Vec<Code> = out.diagnostics.iter().map(|d| d.code).collect();
+        assert_eq!(codes, vec![Code::SlashInName]);
+        assert_eq!(out.diagnostics[0].span.line, 2);
+        assert_eq!(out.items[1].path, "");
+        assert_eq!(out.items[2].path, "");
+        assert_eq!(out.find_path("a/b"), Some(4));
+    }
+
+    #[test]
+    fn escaped_slash_in_a_name_is_rejected() {
+        let out = compile("#[content]\na\\/b\n");
+        assert_eq!(out.diagnostics.len(), 1);
+        assert_eq!(out.diagnostics[0].code, Code::SlashInName);
+    }
+
+    #[test]
+    fn missing_module_is_reported_at_its_path() {
+        let dir = scratch("missing");
+        let main = dir.join("main.neml");
+        std::fs::write(&main, "#[import]\nuse a/b.{c.{d, e as f}}\n").unwrap();
+
+        let config = Engine::init_config(main.to_string_lossy().into_owned());
+        let out = Engine::init().compile(config).unwrap();
+        let missing: Vec<&Diagnostic> = out
+            .diagnostics
+            .iter()
+            .filter(|d| d.code == Code::UnresolvedImport)
+            .collect();
+        assert_eq!(missing.len(), 1);
+        assert_eq!((missing[0].span.line, missing[0].span.column), (2, 5));
+        assert_eq!(missing[0].span.len(), "a/b".len());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn cycles(out: &Compiled) -> Vec<&Diagnostic> {
+        out.diagnostics
+            .iter()
+            .filter(|d| d.code == Code::ImportCycle)
+            .collect()
+    }
+
+    #[test]
+    fn three_file_cycle_is_reported_once() {
+        let dir = scratch("cycle");
+        std::fs::write(
+            dir.join("a.neml"),
+            "#[import]\nuse ./b.x\n\n#[content]\npub a\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("b.neml"),
+            "#[import]\nuse ./c.y\n\n#[content]\npub x\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("c.neml"),
+            "#[import]\n\nuse ./a.a\n\n#[content]\npub y\n",
+        )
+        .unwrap();
+
+        let a = dir.join("a.neml").to_string_lossy().into_owned();
+        let out = Engine::init().compile(Engine::init_config(a)).unwrap();
+        let found = cycles(&out);
+        assert_eq!(found.len(), 1, "{:?}", out.diagnostics);
+
+        let d = found[0];
+        assert_eq!(
+            d.file.as_deref(),
+            Some(dir.join("c.neml").to_str().unwrap())
+        );
+        assert_eq!((d.span.line, d.span.column), (3, 5));
+        assert_eq!(d.notes.len(), 3);
+        for (note, (file, line, decl)) in d.notes.iter().zip([
+            ("a.neml", 2, "use ./b.x"),
+            ("b.neml", 2, "use ./c.y"),
+            ("c.neml", 3, "use ./a.a"),
+        ]) {
+            assert!(
+                note.contains(&format!("{}:{}: {}", file, line, decl)),
+                "{}",
+                note
+            );
+        }
+        assert!(d.message.starts_with("import cycle: "));
+        assert_eq!(d.message.matches("a.neml").count(), 2);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn self_import_is_a_cycle() {
+        let dir = scratch("self");
+        let main = dir.join("s.neml");
+        std::fs::write(&main, "#[import]\nuse ./s.x\n\n#[content]\npub x\n").unwrap();
+
+        let out = Engine::init()
+            .compile(Engine::init_config(main.to_string_lossy().into_owned()))
+            .unwrap();
+        let found = cycles(&out);
+        assert_eq!(found.len(), 1, "{:?}", out.diagnostics);
+        assert_eq!(found[0].notes.len(), 1);
+        assert!(found[0].notes[0].ends_with("s.neml:2: use ./s.x"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn unknown(out: &Compiled) -> Vec<&Diagnostic> {
+        out.diagnostics
+            .iter()
+            .filter(|d| d.code == Code::UnknownName)
+            .collect()
+    }
+
+    #[test]
+    fn names_of_a_failed_use_are_not_reported_again() {
+        let dir = scratch("failed-use");
+        std::fs::write(dir.join("m.neml"), "#[content]\nhidden\npub shown\n").unwrap();
+        let main = dir.join("main.neml");
+        std::fs::write(
+            &main,
+            "#[import]\nuse ./nope.x\nuse ./m.hidden\nuse ./m.gone as g\n\n\
+             #[content]\na + x\nb + hidden\nc + g\nd + y\n",
+        )
+        .unwrap();
+
+        let out = Engine::init()
+            .compile(Engine::init_config(main.to_string_lossy().into_owned()))
+            .unwrap();
+        let codes: Vec<Code> = out.diagnostics.iter().map(|d| d.code).collect();
+        assert!(codes.contains(&Code::UnresolvedImport), "{:?}", codes);
+        assert!(codes.contains(&Code::PrivateAnchor), "{:?}", codes);
+        assert!(codes.contains(&Code::UnknownAnchor), "{:?}", codes);
+
+        let found = unknown(&out);
+        assert_eq!(found.len(), 1, "{:?}", out.diagnostics);
+        assert_eq!(found[0].message, "cannot find `y`");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn unreadable_manifest_is_reported() {
+        let dir = scratch("unreadable");
+        std::fs::write(dir.join("neml.toml"), b"[project]\nname = \"\xff\"\n").unwrap();
+
+        let config = Engine::init_config(dir.to_string_lossy().into_owned());
+        let build = Engine::build(config);
+        let codes: Vec<Code> = build.diagnostics.iter().map(|d| d.code).collect();
+        assert_eq!(codes, vec![Code::ManifestUnreadable]);
+        assert!(build.units.is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn missing_entry_does_not_stop_the_build() {
+        let dir = scratch("entries");
+        std::fs::write(
+            dir.join("neml.toml"),
+            "[project]\nname = \"p\"\nentries = [\"gone.neml\", \"main.neml\"]\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("main.neml"), "#[content]\na\n").unwrap();
+
+        let build = Engine::build(Engine::init_config(dir.to_string_lossy().into_owned()));
+        let codes: Vec<Code> = build.diagnostics.iter().map(|d| d.code).collect();
+        assert_eq!(codes, vec![Code::ManifestEntry]);
+        assert_eq!(build.units.len(), 1);
+        assert_eq!(
+            build.units[0].output,
+            Some(dir.join("main.png").to_string_lossy().into_owned())
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
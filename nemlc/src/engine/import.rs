@@ -1,386 +1,634 @@
 use super::engine::LineContext;
-use crate::utils::{Concat, Finder, Strip};
-use regex::Regex;
+use crate::diag::{Code, Diagnostic, Diagnostics};
+use crate::parse::{PathSep, Span, Token, TokenKind, TokenStream, UseDecl, UseTree};
+use crate::utils::Concat;
 
 #[derive(Debug)]
 pub struct Dependency {
     pub path: Option<String>,
     pub anchor: Option<String>,
     pub alias: Option<String>,
-}
-
-#[derive(Debug)]
-pub struct Element {
-    pub anchor: Option<String>,
-    pub target: Option<String>,
+    pub glob: bool,
+    pub public: bool,
+    pub span: Span,
+    pub path_span: Span,
+    pub decl: Span,
+    pub loaded: bool,
 }
 
 #[derive(Debug)]
 pub struct Import {
     pub data_list: Vec<Dependency>,
+    pub pending: Vec<Token>,
+    pub errors: Vec<Diagnostic>,
+}
+
+impl Dependency {
+    /*
+     * The name a plain `use` binds: its alias, or the last segment of
+     * its anchor. A glob binds no single name.
+     */
+    pub fn name(&self) -> Option<String> {
+        if self.glob {
+            return None;
+        }
+        match (&self.alias, &self.anchor) {
+            (Some(alias), _) => Some(alias.clone()),
+            (None, Some(anchor)) => Some(anchor.rsplit('/').next().unwrap_or(anchor).to_string()),
+            (None, None) => None,
+        }
+    }
 }
 
 impl Import {
     pub fn init() -> Self {
         Import {
             data_list: Vec::new(),
+            pending: Vec::new(),
+            errors: Vec::new(),
         }
     }
 
-    pub fn plain_alias_parse(&mut self, tc: Vec<&str>) -> Result<Dependency, std::io::Error> {
-        let dst = tc[0];
-        let alias = tc[1];
-
-        let ele = self.plain_target_parse(dst).unwrap();
-        let anc = ele.anchor;
-        match anc {
-            Some(anchor) => {
-                let dep1 = Dependency {
-                    path: Some(anchor),
-                    anchor: ele.target,
-                    alias: Some(alias.clone().to_string()),
-                };
-
-                return Ok(dep1);
-            }
-            None => {
-                let dep1 = Dependency {
-                    path: Some("/unknow".to_string()),
-                    anchor: Some("/unknow".to_string()),
-                    alias: Some("unknow".to_string()),
-                };
-
-                return Ok(dep1);
-            }
+    pub fn plain_segment(&mut self, ts: &mut TokenStream) -> Result<(String, Span), Diagnostic> {
+        match ts.peek() {
+            Some(t) => match &t.kind {
+                TokenKind::Ident(x) | TokenKind::Str(x) if !x.trim().is_empty() => {
+                    ts.bump();
+                    Ok((x.trim().to_string(), t.span))
+                }
+                TokenKind::Invalid(_) => {
+                    Err(
+                        Diagnostic::error(Code::UnterminatedString, "unterminated string", t.span)
+                            .with_help("add a closing `\"`"),
+                    )
+                }
+                k => Err(Diagnostic::error(
+                    Code::UnexpectedToken,
+                    format!("expected a name, found {}", k.describe()),
+                    t.span,
+                )),
+            },
+            None => Err(Diagnostic::error(
+                Code::UnexpectedToken,
+                "expected a name, found end of declaration",
+                ts.last_span(),
+            )),
         }
     }
 
-    pub fn plain_target_parse(&mut self, _dest: &str) -> Result<Element, std::io::Error> {
-        let dep = Element {
-            anchor: Some("/unknow".to_string()),
-            target: Some("unknow".to_string()),
-        };
+    pub fn plain_atom_parse(
+        &mut self,
+        ts: &mut TokenStream,
+        name: String,
+        span: Span,
+    ) -> Result<UseTree, Diagnostic> {
+        if ts.eat(&TokenKind::As).is_some() {
+            let (alias, alias_span) = self.plain_segment(ts)?;
+            return Ok(UseTree::Name {
+                name,
+                alias: Some(alias),
+                span: span.to(alias_span),
+            });
+        }
 
-        return Ok(dep);
+        Ok(UseTree::Name {
+            name,
+            alias: None,
+            span,
+        })
     }
 
-    pub fn line_seg(s: &str) -> Result<Vec<String>, String> {
-        let mut out_data = Vec::new();
-        let mut index_now = 0;
-
-        while index_now < s.len() {
-            let mut next_delta = 0;
-
-            let next = Strip::next_strip(s, index_now, ' ', true);
-            let next_index = next.unwrap();
-            next_delta += next_index;
-
-            let ai = Finder::next_point(s, index_now + next_delta, '.', true);
-            match ai {
-                Ok(c) => {
-                    next_delta = next_delta + c + 1;
+    pub fn plain_mul_parse(
+        &mut self,
+        ts: &mut TokenStream,
+        open: Span,
+    ) -> Result<UseTree, Diagnostic> {
+        let mut items = Vec::new();
+        loop {
+            if let Some(close) = ts.eat(&TokenKind::RBrace) {
+                return Ok(UseTree::Group {
+                    items,
+                    span: open.to(close.span),
+                });
+            }
 
-                    let next = Strip::next_strip(s, index_now + next_delta, ' ', true);
+            if ts.is_empty() {
+                return Err(Diagnostic::error(Code::UnclosedGroup, "unclosed `{`", open)
+                    .with_help("add a closing `}`"));
+            }
 
-                    let next_index = next.unwrap();
-                    next_delta += next_index;
+            match self.plain_parse_impl(ts) {
+                Ok(tree) => items.push(tree),
+                Err(e) => {
+                    self.errors.push(e);
+                    Import::skip_item(ts);
+                }
+            }
 
-                    if s.as_bytes()[index_now + next_delta] == ('{' as u8) {
-                        next_delta += 1;
-                        let gap = Finder::next_point(s, index_now + next_delta, '}', true);
-                        let gap_index = gap.unwrap();
+            if ts.eat(&TokenKind::Comma).is_some() {
+                continue;
+            }
 
-                        next_delta = next_delta + gap_index + 1;
+            if let Some(t) = ts.peek() {
+                if t.kind != TokenKind::RBrace {
+                    self.errors.push(
+                        Diagnostic::error(
+                            Code::UnexpectedToken,
+                            format!("expected `,` or `}}`, found {}", t.kind.describe()),
+                            t.span,
+                        )
+                        .with_label(open, "group opened here"),
+                    );
+                    Import::skip_item(ts);
+                    ts.eat(&TokenKind::Comma);
+                }
+            }
+        }
+    }
 
-                        let next = Strip::next_strip(s, index_now + next_delta, ' ', true);
+    /*
+     * Recovery inside a `{...}` group: drop the broken item and resume
+     * at the next `,` or `}` of the same group.
+     */
+    pub fn skip_item(ts: &mut TokenStream) {
+        let mut depth = 0;
+        while let Some(t) = ts.peek() {
+            match t.kind {
+                TokenKind::Comma | TokenKind::RBrace if depth == 0 => return,
+                TokenKind::LBrace => depth += 1,
+                TokenKind::RBrace => depth -= 1,
+                _ => {}
+            }
+            ts.bump();
+        }
+    }
 
-                        let next_index = next.unwrap();
+    pub fn plain_parse_impl(&mut self, ts: &mut TokenStream) -> Result<UseTree, Diagnostic> {
+        if let Some(open) = ts.eat(&TokenKind::LBrace) {
+            return self.plain_mul_parse(ts, open.span);
+        }
 
-                        next_delta += next_index;
-                        if (index_now + next_delta) >= s.len() {
-                            let raw_wait_str = &s[index_now..(next_delta + index_now)];
-                            let wait_str = raw_wait_str.trim().to_string();
-                            out_data.push(wait_str);
-                        } else {
-                            if s.as_bytes()[index_now + next_delta] != (',' as u8) {
-                                return Err(String::from("Invalid"));
-                            }
+        if let Some(star) = ts.eat(&TokenKind::Star) {
+            return Ok(UseTree::Glob { span: star.span });
+        }
 
-                            let raw_wait_str = &s[index_now..(next_delta + index_now)];
-                            let wait_str = raw_wait_str.trim().to_string();
-                            out_data.push(wait_str);
+        let (name, span) = match ts.peek_kind() {
+            Some(TokenKind::Slash) | Some(TokenKind::Dot) => return self.plain_prefix_parse(ts),
+            _ => self.plain_segment(ts)?,
+        };
 
-                            next_delta += 1;
-                        }
-                    } else {
-                        let gap = Finder::next_point(s, index_now + next_delta, ',', true);
+        let sep = if ts.eat(&TokenKind::Slash).is_some() {
+            PathSep::Slash
+        } else if ts.eat(&TokenKind::Dot).is_some() {
+            PathSep::Dot
+        } else {
+            return self.plain_atom_parse(ts, name, span);
+        };
 
-                        let gap_index = gap.unwrap();
+        let next = self.plain_parse_impl(ts)?;
 
-                        next_delta = next_delta + gap_index + 1;
+        Ok(UseTree::Path {
+            name,
+            name_span: span,
+            sep,
+            span: span.to(next.span()),
+            next: Box::new(next),
+        })
+    }
 
-                        let raw_wait_str = &s[index_now..(next_delta + index_now - 1)];
-                        let wait_str = raw_wait_str.trim().to_string();
-                        out_data.push(wait_str);
-                    }
-                }
-                Err(_) => {
-                    println!("Invalid");
-                    return Err(String::from("Invalid"));
+    /*
+     * The start of a module path that is not a library path: `/` for
+     * the project root, `./` and `../` for the importing file. They are
+     * kept as segments named ``, `.` and `..` for `flatten`.
+     */
+    pub fn plain_prefix_parse(&mut self, ts: &mut TokenStream) -> Result<UseTree, Diagnostic> {
+        let (name, span) = match ts.eat(&TokenKind::Slash) {
+            Some(slash) => (String::new(), slash.span),
+            None => {
+                let dot = ts.eat(&TokenKind::Dot).map(|t| t.span).unwrap_or_default();
+                match ts.eat(&TokenKind::Dot) {
+                    Some(second) => ("..".to_string(), dot.to(second.span)),
+                    None => (".".to_string(), dot),
                 }
             }
+        };
 
-            index_now += next_delta;
+        if !name.is_empty() && ts.eat(&TokenKind::Slash).is_none() {
+            let found = ts
+                .peek()
+                .map(|t| t.kind.describe())
+                .unwrap_or_else(|| "end of declaration".to_string());
+            return Err(Diagnostic::error(
+                Code::UnexpectedToken,
+                format!("expected `/` after `{}`, found {}", name, found),
+                ts.peek().map(|t| t.span).unwrap_or(span),
+            )
+            .with_help(format!("write `{}/<module>`", name)));
         }
 
-        return Ok(out_data);
+        let next = self.plain_parse_impl(ts)?;
+
+        Ok(UseTree::Path {
+            name,
+            name_span: span,
+            sep: PathSep::Slash,
+            span: span.to(next.span()),
+            next: Box::new(next),
+        })
     }
 
-    pub fn plain_mul_anchor_parse(
-        &mut self,
-        tc0: String,
-    ) -> Result<Vec<Dependency>, std::io::Error> {
-        let mut out_data = Vec::new();
-        let tokens = Import::line_seg(tc0.as_str());
-        match tokens {
-            Ok(tc) => {
-                for s in tc {
-                    let mul_dep = self.plain_parse_target(s, "".to_string()).unwrap();
-                    for d in mul_dep {
-                        out_data.push(d);
-                    }
+    /*
+     * `/a` keeps its leading `/`, every other segment is joined with one.
+     */
+    pub fn join_path(path: &str, name: &str) -> String {
+        match (path, name) {
+            ("", "") => "/".to_string(),
+            ("/", _) => format!("/{}", name),
+            _ => Concat::raw_concat(path.to_string(), "/".to_string(), name.to_string()),
+        }
+    }
+
+    /*
+     * The first `.` splits the module path from the anchor: everything
+     * before it names a file, everything after it names a node inside
+     * that file.
+     *
+     *   a/b.{c.{d, e as f}, g}  =>  (a/b, c/d) (a/b, c/e as f) (a/b, g)
+     *   a/b.{*, c.*}            =>  (a/b, all) (a/b, all of c)
+     *
+     * `path_span` covers the module path read so far.
+     */
+    pub fn flatten(
+        tree: &UseTree,
+        path: &str,
+        path_span: Span,
+        anchor: Option<&str>,
+        out_data: &mut Vec<Dependency>,
+        errors: &mut Vec<Diagnostic>,
+    ) {
+        match tree {
+            UseTree::Path {
+                name,
+                name_span,
+                sep,
+                next,
+                ..
+            } => match (anchor, sep) {
+                (None, _) => {
+                    let span = match path.is_empty() {
+                        true => *name_span,
+                        false => path_span.to(*name_span),
+                    };
+                    let anchor = match sep {
+                        PathSep::Slash => None,
+                        PathSep::Dot => Some(""),
+                    };
+                    let path = Import::join_path(path, name);
+                    Import::flatten(next, &path, span, anchor, out_data, errors)
+                }
+                (Some(_), _) if matches!(name.as_str(), "" | "." | "..") => errors.push(
+                    Diagnostic::error(
+                        Code::InvalidModulePath,
+                        "a module path cannot start after an anchor",
+                        tree.span(),
+                    )
+                    .with_help("move it into its own `use` declaration"),
+                ),
+                (Some(a), _) => {
+                    let a = Concat::raw_concat(a.to_string(), "/".to_string(), name.clone());
+                    Import::flatten(next, path, path_span, Some(&a), out_data, errors)
+                }
+            },
+            UseTree::Group { items, .. } => {
+                for item in items {
+                    Import::flatten(item, path, path_span, anchor, out_data, errors);
                 }
             }
-            Err(_) => {}
+            UseTree::Glob { span } => match anchor {
+                Some(a) => out_data.push(Dependency {
+                    path: Some(path.to_string()),
+                    anchor: Some(a.to_string()),
+                    alias: None,
+                    glob: true,
+                    public: false,
+                    span: *span,
+                    path_span,
+                    decl: Span::default(),
+                    loaded: false,
+                }),
+                None => errors.push(
+                    Diagnostic::error(
+                        Code::MissingAnchor,
+                        format!("`{}/*` does not name anchors", path),
+                        *span,
+                    )
+                    .with_help(format!("write `{}.*` to import every anchor", path)),
+                ),
+            },
+            UseTree::Name { name, alias, span } => match anchor {
+                Some(a) => {
+                    out_data.push(Dependency {
+                        path: Some(path.to_string()),
+                        anchor: Some(Concat::raw_concat(
+                            a.to_string(),
+                            "/".to_string(),
+                            name.clone(),
+                        )),
+                        alias: alias.clone(),
+                        glob: false,
+                        public: false,
+                        span: *span,
+                        path_span,
+                        decl: Span::default(),
+                        loaded: false,
+                    });
+                }
+                None => {
+                    let full = Import::join_path(path, name);
+                    errors.push(
+                        Diagnostic::error(
+                            Code::MissingAnchor,
+                            format!("`{}` does not name an anchor", full),
+                            *span,
+                        )
+                        .with_help(format!("write `{}.<anchor>` to import an anchor", full)),
+                    );
+                }
+            },
         }
-
-        Ok(out_data)
     }
 
-    pub fn plain_mul_parse(&mut self, tc0: String) -> Result<Vec<Dependency>, std::io::Error> {
-        let mut out_data = Vec::new();
-        let tokens = tc0.split(",");
-        let tc: Vec<&str> = tokens.collect();
-
-        for s in tc {
-            let line = s.trim().to_string();
-
-            let dep = self.plain_atom_parse(line).unwrap();
+    pub fn extract(&mut self, ts: &mut TokenStream) -> Option<UseTree> {
+        let tree = match self.plain_parse_impl(ts) {
+            Ok(tree) => tree,
+            Err(e) => {
+                self.errors.push(e);
+                return None;
+            }
+        };
 
-            out_data.push(dep);
+        if let Some(t) = ts.peek() {
+            self.errors.push(Diagnostic::error(
+                Code::UnexpectedToken,
+                format!("unexpected {} after use declaration", t.kind.describe()),
+                t.span,
+            ));
         }
 
-        Ok(out_data)
+        Some(tree)
     }
 
-    pub fn plain_atom_parse(&mut self, tc0: String) -> Result<Dependency, std::io::Error> {
-        let tokens = tc0.split(" as ");
-        let tc: Vec<&str> = tokens.collect();
-
-        let plain_count = tc.len();
-        if plain_count == 1 {
-            let anchor = tc[0].trim().to_string();
-            let anchor_new = Strip::colon_strip(anchor);
-            let anchor_real = anchor_new.trim().to_string();
-            if anchor_real.len() < 1 {
-                let dep = Dependency {
-                    path: Some("/unknow".to_string()),
-                    anchor: Some("/unknow".to_string()),
-                    alias: Some("unknow".to_string()),
-                };
-
-                return Ok(dep);
+    pub fn parse_decl(&mut self, tokens: &[Token]) -> Option<UseDecl> {
+        let mut ts = TokenStream::new(tokens);
+        let vis = ts.eat(&TokenKind::Pub);
+        let kw = match ts.eat(&TokenKind::Use) {
+            Some(kw) => kw,
+            None => {
+                self.errors.push(Import::expected_use(ts.last_span()));
+                return None;
             }
+        };
 
-            let ele = Dependency {
-                path: None,
-                anchor: Some(anchor_real),
-                alias: None,
-            };
-
-            return Ok(ele);
-        } else if plain_count == 2 {
-            let target = tc[0].trim().to_string();
-            let alias = tc[1].trim().to_string();
-
-            let new_target = Strip::colon_strip(target);
-            let new_alias = Strip::colon_strip(alias);
-
-            let real_target = new_target.trim().to_string();
-            let real_alias = new_alias.trim().to_string();
-
-            let dep = Dependency {
-                path: None,
-                anchor: Some(real_target),
-                alias: Some(real_alias),
-            };
-
-            return Ok(dep);
-        } else {
-            //Error
-            let dep = Dependency {
-                path: Some("/unknow".to_string()),
-                anchor: Some("/unknow".to_string()),
-                alias: Some("unknow".to_string()),
-            };
-
-            return Ok(dep);
+        let tree = self.extract(&mut ts)?;
+        let span = vis.unwrap_or(kw).span.to(tree.span());
+        let mut deps = Vec::new();
+        Import::flatten(
+            &tree,
+            "",
+            Span::default(),
+            None,
+            &mut deps,
+            &mut self.errors,
+        );
+        for d in &mut deps {
+            d.public = vis.is_some();
+            d.decl = span;
         }
-    }
+        self.data_list.extend(deps);
 
-    pub fn plain_parse(&mut self, tc0: Vec<&str>) -> Result<Vec<Dependency>, std::io::Error> {
-        let tot_len = tc0.len();
-        let prefix = Concat::slash_concat(&tc0, tot_len - 1, "".to_string());
-
-        let obj = tc0[tot_len - 1];
-        let raw_obj = obj.to_string();
+        Some(UseDecl {
+            public: vis.is_some(),
+            span,
+            tree,
+        })
+    }
 
-        return self.plain_parse_impl(raw_obj, prefix);
+    pub fn expected_use(span: Span) -> Diagnostic {
+        Diagnostic::error(Code::ExpectedUse, "expected `use` declaration", span)
+            .with_help("the #[import] section only holds `use` declarations")
     }
 
-    pub fn plain_parse_target(
-        &mut self,
-        raw_obj: String,
-        prefix: String,
-    ) -> Result<Vec<Dependency>, std::io::Error> {
-        let tokens = raw_obj.split(".");
-        let tc: Vec<&str> = tokens.collect();
-        let tc_len = tc.len();
-        if tc_len < 2 {
-            let dep1 = Dependency {
-                path: Some("/unknow".to_string()),
-                anchor: Some("/unknow".to_string()),
-                alias: Some("unknow".to_string()),
-            };
+    pub fn brace_depth(tokens: &[Token]) -> isize {
+        tokens.iter().fold(0, |depth, t| match t.kind {
+            TokenKind::LBrace => depth + 1,
+            TokenKind::RBrace => depth - 1,
+            _ => depth,
+        })
+    }
 
-            let mut out_data = Vec::new();
-            out_data.push(dep1);
+    /*
+     * A declaration may span several lines while a `{` is open. A new
+     * `use` line closes whatever was pending, so a missing `}` costs one
+     * declaration instead of swallowing the rest of the section.
+     */
+    pub fn parse_item(&mut self, linec: &LineContext, diag: &mut Diagnostics) -> Vec<UseDecl> {
+        let mut out_data = Vec::new();
+        let tokens: Vec<Token> = linec
+            .tokens
+            .iter()
+            .filter(|t| !t.is_trivia())
+            .cloned()
+            .collect();
+
+        let first = match tokens.first() {
+            Some(t) => t,
+            None => return out_data,
+        };
 
-            return Ok(out_data);
+        let starts_use = matches!(first.kind, TokenKind::Use | TokenKind::Pub);
+        if starts_use {
+            out_data.extend(self.finish(diag));
+        } else if self.pending.is_empty() {
+            diag.push(Import::expected_use(
+                first.span.to(tokens[tokens.len() - 1].span),
+            ));
+            return out_data;
         }
 
-        let anchor = tc[0].to_string();
-        let anc_len = anchor.len();
-        let tar_off = anc_len + 1;
-
-        let tar = &raw_obj[tar_off..];
+        self.pending.extend(tokens);
 
-        if tar.starts_with("{") && raw_obj.ends_with("}") {
-            let tar_len = tar.len();
-            if tar_len < 3 {
-                let dep = Dependency {
-                    path: Some("/unknow".to_string()),
-                    anchor: Some("/unknow".to_string()),
-                    alias: Some("unknow".to_string()),
-                };
+        if Import::brace_depth(&self.pending) <= 0 {
+            out_data.extend(self.finish(diag));
+        }
 
-                let mut out_data = Vec::new();
-                out_data.push(dep);
+        out_data
+    }
 
-                return Ok(out_data);
-            }
+    pub fn finish(&mut self, diag: &mut Diagnostics) -> Option<UseDecl> {
+        if self.pending.is_empty() {
+            return None;
+        }
 
-            let mul_tar_obj = tar[1..(tar_len - 1)].to_string();
-            let path = Concat::raw_concat(prefix.clone(), "/".to_string(), anchor);
-            let mut mul_dep = self.plain_mul_parse(mul_tar_obj).unwrap();
-            for ref mut d in &mut mul_dep {
-                d.path = Some(path.clone());
-            }
+        let tokens = std::mem::take(&mut self.pending);
+        let decl = self.parse_decl(&tokens);
+        for e in self.errors.drain(..) {
+            diag.push(e);
+        }
 
-            return Ok(mul_dep);
-        } else {
-            let mut dep = self.plain_atom_parse(tar.to_string()).unwrap();
-            let path = Concat::raw_concat(prefix.clone(), "/".to_string(), anchor);
+        decl
+    }
+}
 
-            dep.path = Some(path);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::{LexMode, Lexer};
+
+    fn parse(lines: &[&str]) -> (Vec<Dependency>, Vec<Code>) {
+        let mut import = Import::init();
+        let mut diag = Diagnostics::new();
+        let mut offset = 0;
+        for (i, line) in lines.iter().enumerate() {
+            let linec = LineContext {
+                line_number: i + 1,
+                offset,
+                text: line.to_string(),
+                tokens: Lexer::new(LexMode::Import).tokenize_line(line, i + 1, offset),
+                lossy: false,
+            };
+            import.parse_item(&linec, &mut diag);
+            offset += line.len() + 1;
+        }
+        import.finish(&mut diag);
 
-            let mut out_data = Vec::new();
-            out_data.push(dep);
+        (import.data_list, diag.list.iter().map(|d| d.code).collect())
+    }
 
-            return Ok(out_data);
-        }
+    /*
+     * `path.anchor`, `path.anchor.*` or `path.anchor as alias`.
+     */
+    fn show(deps: &[Dependency]) -> Vec<String> {
+        deps.iter()
+            .map(|d| {
+                let mut s = format!(
+                    "{}.{}",
+                    d.path.as_deref().unwrap_or(""),
+                    d.anchor.as_deref().unwrap_or("")
+                );
+                if d.glob {
+                    s.push_str(".*");
+                }
+                if let Some(alias) = &d.alias {
+                    s.push_str(&format!(" as {}", alias));
+                }
+                s
+            })
+            .collect()
     }
 
-    pub fn plain_parse_impl(
-        &mut self,
-        raw_obj: String,
-        prefix: String,
-    ) -> Result<Vec<Dependency>, std::io::Error> {
-        if raw_obj.starts_with("{") && raw_obj.ends_with("}") {
-            let mul_obj = raw_obj.len();
-            if mul_obj < 3 {
-                let dep = Dependency {
-                    path: Some("/unknow".to_string()),
-                    anchor: Some("/unknow".to_string()),
-                    alias: Some("unknow".to_string()),
-                };
-
-                let mut out_data = Vec::new();
-                out_data.push(dep);
-
-                return Ok(out_data);
-            }
+    #[test]
+    fn single_anchor() {
+        let (deps, errors) = parse(&["use a/b.c"]);
+        assert_eq!(show(&deps), vec!["a/b.c"]);
+        assert!(errors.is_empty());
+        assert!(!deps[0].public);
+    }
 
-            let mul_raw_obj = raw_obj[1..(mul_obj - 1)].to_string();
+    #[test]
+    fn nested_groups() {
+        let (deps, errors) = parse(&["use a/b.{c.{d, e as f}, g}"]);
+        assert_eq!(show(&deps), vec!["a/b.c/d", "a/b.c/e as f", "a/b.g"]);
+        assert!(errors.is_empty());
+    }
 
-            let mut mul_a_dep = self.plain_mul_anchor_parse(mul_raw_obj).unwrap();
+    #[test]
+    fn trailing_commas() {
+        let (deps, errors) = parse(&["use a.{b, c.{d,},}"]);
+        assert_eq!(show(&deps), vec!["a.b", "a.c/d"]);
+        assert!(errors.is_empty());
+    }
 
-            for ref mut d in &mut mul_a_dep {
-                match &d.path {
-                    Some(p) => {
-                        let path =
-                            Concat::raw_concat(prefix.clone(), "/".to_string(), p.to_string());
-                        d.path = Some(path.clone());
-                    }
-                    None => {}
-                }
-            }
+    #[test]
+    fn aliases() {
+        let (deps, errors) = parse(&["pub use ./a.b as c", "use /x/y.\"z w\" as v"]);
+        assert_eq!(show(&deps), vec!["./a.b as c", "/x/y.z w as v"]);
+        assert!(errors.is_empty());
+        assert!(deps[0].public);
+        assert!(!deps[1].public);
+    }
 
-            return Ok(mul_a_dep);
-        }
+    #[test]
+    fn groups_can_span_lines() {
+        let (deps, errors) = parse(&["use a.{", "  b,", "  c as d // why", "}", "use e.f"]);
+        assert_eq!(show(&deps), vec!["a.b", "a.c as d", "e.f"]);
+        assert!(errors.is_empty());
+        assert_eq!(deps[0].decl.line, 1);
+        assert_eq!(deps[1].span.line, 3);
+    }
 
-        return self.plain_parse_target(raw_obj, prefix);
+    #[test]
+    fn globs() {
+        let (deps, errors) = parse(&["use a/b.{*, c.*}"]);
+        assert_eq!(show(&deps), vec!["a/b..*", "a/b.c.*"]);
+        assert!(errors.is_empty());
     }
 
-    pub fn alis_simple_process(&mut self, s: &String) -> Result<Vec<Dependency>, std::io::Error> {
-        let tokens = s.split("/");
-        let tc: Vec<&str> = tokens.collect();
-        let ptc = self.plain_parse(tc);
-        return ptc;
+    #[test]
+    fn paths_need_an_anchor() {
+        let (deps, errors) = parse(&["use a/b", "use a/*", "use a.{b, ./c}"]);
+        assert_eq!(show(&deps), vec!["a.b"]);
+        assert_eq!(
+            errors,
+            vec![
+                Code::MissingAnchor,
+                Code::MissingAnchor,
+                Code::InvalidModulePath
+            ]
+        );
     }
 
-    pub fn extract(&mut self, s: &String) -> Result<Vec<Dependency>, std::io::Error> {
-        let out_data = Vec::new();
-        let r1 = Regex::new(r"(.+/)?(.*)").unwrap();
-        if r1.is_match(&s) {
-            let ptc = self.alis_simple_process(s);
-            println!("import = {:#?}", ptc);
+    #[test]
+    fn a_missing_comma_drops_one_item() {
+        let (deps, errors) = parse(&["use a.{b c, d}"]);
+        assert_eq!(show(&deps), vec!["a.b", "a.d"]);
+        assert_eq!(errors, vec![Code::UnexpectedToken]);
+    }
 
-            return ptc;
-        }
+    #[test]
+    fn an_empty_item_is_skipped() {
+        let (deps, errors) = parse(&["use a.{b, , d}"]);
+        assert_eq!(show(&deps), vec!["a.b", "a.d"]);
+        assert_eq!(errors, vec![Code::UnexpectedToken]);
+    }
 
-        Ok(out_data)
+    #[test]
+    fn recovery_stays_inside_the_nested_group() {
+        let (deps, errors) = parse(&["use a.{b.{c d, e}, f}"]);
+        assert_eq!(show(&deps), vec!["a.b/c", "a.b/e", "a.f"]);
+        assert_eq!(errors, vec![Code::UnexpectedToken]);
     }
 
-    pub fn parse_item(&mut self, linec: &LineContext) {
-        let line = &linec.seg;
-        for rst in line {
-            let rs = rst.trim().to_string();
+    #[test]
+    fn an_unclosed_group_ends_at_the_next_use() {
+        let (deps, errors) = parse(&["use a.{b,", "  c", "use d.e"]);
+        assert_eq!(show(&deps), vec!["d.e"]);
+        assert_eq!(errors, vec![Code::UnclosedGroup]);
+    }
 
-            if rs.len() == 0 {
-                continue;
-            }
-            let new_s = rs.clone();
-            if new_s.starts_with("//") {
-                continue;
-            }
+    #[test]
+    fn text_after_the_declaration() {
+        let (deps, errors) = parse(&["use a.b c", "b.c"]);
+        assert_eq!(show(&deps), vec!["a.b"]);
+        assert_eq!(errors, vec![Code::UnexpectedToken, Code::ExpectedUse]);
+    }
 
-            if !new_s.starts_with("use ") {
-                println!("Error line: {} {:?}", linec.line_number, new_s)
-            }
+    #[test]
+    fn path_span_covers_the_module_path() {
+        let (deps, _) = parse(&["use a/b.{c.{d, e as f}}"]);
+        assert!(deps.iter().all(|d| d.path_span == Span::new(4, 7, 1, 5)));
 
-            let s = new_s[4..].to_string();
-            let _r = self.extract(&s);
-        }
+        let (deps, _) = parse(&["use ../x/y.z", "pub use /p.q"]);
+        assert_eq!(deps[0].path_span, Span::new(4, 10, 1, 5));
+        assert_eq!(deps[1].path_span, Span::new(21, 23, 2, 9));
     }
 }
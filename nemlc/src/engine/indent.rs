@@ -0,0 +1,176 @@
+use crate::diag::{Code, Diagnostic, Diagnostics};
+use crate::parse::Span;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IndentStyle {
+    Spaces(usize),
+    Tabs,
+    Infer,
+}
+
+#[derive(Debug)]
+pub struct Indentation {
+    pub style: IndentStyle,
+    pub strict: bool,
+    pub unit: Option<IndentStyle>,
+    pub origin: Option<Span>,
+}
+
+impl IndentStyle {
+    pub fn describe(&self) -> String {
+        match self {
+            IndentStyle::Spaces(1) => "1 space".to_string(),
+            IndentStyle::Spaces(n) => format!("{} spaces", n),
+            IndentStyle::Tabs => "tabs".to_string(),
+            IndentStyle::Infer => "inferred".to_string(),
+        }
+    }
+}
+
+impl Indentation {
+    pub fn new(style: IndentStyle, strict: bool) -> Self {
+        let unit = match style {
+            IndentStyle::Infer | IndentStyle::Spaces(0) => None,
+            s => Some(s),
+        };
+
+        Indentation {
+            style,
+            strict,
+            unit,
+            origin: None,
+        }
+    }
+
+    fn mixed(&self, span: Span, unit: IndentStyle) -> Diagnostic {
+        let message = match unit {
+            IndentStyle::Tabs => "spaces in tab indentation",
+            _ => "tabs in space indentation",
+        };
+
+        let mut d = if self.strict {
+            Diagnostic::error(Code::MixedIndent, message, span)
+        } else {
+            Diagnostic::warning(Code::MixedIndent, message, span)
+        };
+
+        if let Some(origin) = self.origin {
+            d = d.with_label(
+                origin,
+                format!("indentation unit set to {}", unit.describe()),
+            );
+        }
+
+        d.with_help(format!("indent with {} only", unit.describe()))
+    }
+
+    /*
+     * The level a line that failed to parse would have had, without
+     * checking its indentation: odd widths round down, and before the
+     * unit is known any indentation is one level.
+     */
+    pub fn rough_level(&self, indent: &str) -> usize {
+        let tabs = indent.chars().filter(|&c| c == '\t').count();
+        let spaces = indent.chars().filter(|&c| c == ' ').count();
+
+        match self.unit {
+            _ if tabs == 0 && spaces == 0 => 0,
+            Some(IndentStyle::Tabs) => tabs.max(1),
+            Some(IndentStyle::Spaces(n)) => ((spaces + tabs * n) / n).max(1),
+            _ => 1,
+        }
+    }
+
+    /*
+     * Turns the leading whitespace of a content line into a level.
+     *
+     * In non-strict mode a tab inside space indentation counts as one
+     * unit and spaces trailing tab indentation are ignored, both with
+     * a warning. In strict mode either is an error and the line is
+     * dropped.
+     */
+    pub fn level(
+        &mut self,
+        indent: &str,
+        span: Span,
+        diag: &mut Diagnostics,
+    ) -> Result<usize, Diagnostic> {
+        let tabs = indent.chars().filter(|&c| c == '\t').count();
+        let spaces = indent.chars().filter(|&c| c == ' ').count();
+
+        if tabs == 0 && spaces == 0 {
+            return Ok(0);
+        }
+
+        let unit = match self.unit {
+            Some(u) => u,
+            None => {
+                if tabs > 0 && spaces > 0 {
+                    return Err(Diagnostic::error(
+                        Code::MixedIndent,
+                        "cannot infer the indentation unit from mixed tabs and spaces",
+                        span,
+                    )
+                    .with_help("indent the first child with only tabs or only spaces"));
+                }
+
+                let u = if tabs > 0 {
+                    IndentStyle::Tabs
+                } else {
+                    IndentStyle::Spaces(spaces)
+                };
+                self.unit = Some(u);
+                self.origin = Some(span);
+                u
+            }
+        };
+
+        match unit {
+            IndentStyle::Tabs => {
+                if spaces > 0 {
+                    let d = self.mixed(span, unit);
+                    if d.is_error() {
+                        return Err(d);
+                    }
+                    diag.push(d);
+                }
+
+                Ok(tabs)
+            }
+            IndentStyle::Spaces(n) => {
+                if tabs > 0 {
+                    let d = self.mixed(span, unit);
+                    if d.is_error() {
+                        return Err(d);
+                    }
+                    diag.push(d);
+                }
+
+                let width = spaces + tabs * n;
+                if width % n != 0 {
+                    let below = width / n * n;
+                    let mut d = Diagnostic::error(
+                        Code::OddIndent,
+                        format!(
+                            "indentation of {} columns is not a multiple of {}",
+                            width, n
+                        ),
+                        span,
+                    );
+                    if let Some(origin) = self.origin {
+                        d = d.with_label(origin, format!("indentation unit of {} set here", n));
+                    }
+
+                    return Err(d.with_help(format!(
+                        "expected the text at column {} or {}",
+                        below + 1,
+                        below + n + 1
+                    )));
+                }
+
+                Ok(width / n)
+            }
+            IndentStyle::Infer => Ok(0),
+        }
+    }
+}
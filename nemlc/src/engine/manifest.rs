@@ -0,0 +1,678 @@
+use super::indent::IndentStyle;
+use crate::diag::{Code, Diagnostic, SourceFile};
+use crate::parse::{Scanner, Span};
+use std::path::{Component, Path, PathBuf};
+
+/*
+ * neml.toml, a small subset of TOML:
+ *
+ *   [project]
+ *   name = "diagrams"
+ *   entries = ["src/main.neml", "src/deploy.neml"]
+ *   include = ["lib"]
+ *   indent = 2            # 2, 4, "tabs" or "infer"
+ *   strict = false
+ *   lenient = false
+ *
+ *   [dependencies]
+ *   shapes = { path = "../shapes" }
+ *
+ *   [dependencies.icons]  # the same as `icons = { path = "../icons" }`
+ *   path = "../icons"
+ *
+ *   [output]
+ *   dir = "out"
+ *   format = "png"
+ *
+ * Values are strings with `\"`, `\\`, `\n` and `\t` escapes, integers,
+ * booleans, arrays and inline tables; floats, multi-line strings and
+ * dotted keys are not read. Relative paths are taken from the directory
+ * holding the manifest.
+ */
+#[derive(Debug, Clone)]
+pub struct Manifest {
+    pub root: PathBuf,
+    pub name: String,
+    pub entries: Vec<PathBuf>,
+    pub entry_spans: Vec<Span>,
+    pub include: Vec<PathBuf>,
+    pub deps: Vec<PathDep>,
+    pub indent: Option<IndentStyle>,
+    pub strict: bool,
+    pub lenient: bool,
+    pub output: Option<PathBuf>,
+    pub format: String,
+    pub diagnostics: Vec<Diagnostic>,
+    pub source: SourceFile,
+}
+
+#[derive(Debug, Clone)]
+pub struct PathDep {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Str(String),
+    Int(i64),
+    Bool(bool),
+    Array(Vec<(Value, Span)>),
+    Table(Vec<(String, Value, Span)>),
+}
+
+struct Entry {
+    table: Vec<String>,
+    key: String,
+    key_span: Span,
+    value: Value,
+    span: Span,
+}
+
+const ESCAPES: &[(char, char)] = &[('"', '"'), ('\\', '\\'), ('n', '\n'), ('t', '\t')];
+
+struct Cursor<'a> {
+    scan: Scanner<'a>,
+}
+
+impl Value {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            Value::Str(_) => "a string",
+            Value::Int(_) => "an integer",
+            Value::Bool(_) => "a boolean",
+            Value::Array(_) => "an array",
+            Value::Table(_) => "a table",
+        }
+    }
+}
+
+impl<'a> Cursor<'a> {
+    /*
+     * Skips spaces and comments; newlines too when `lines` is set, as
+     * inside arrays and between entries.
+     */
+    fn skip(&mut self, lines: bool) {
+        while let Some(c) = self.scan.peek() {
+            match c {
+                ' ' | '\t' | '\r' => self.scan.pos += 1,
+                '\n' if lines => self.scan.pos += 1,
+                '#' => {
+                    while self.scan.peek().is_some_and(|c| c != '\n') {
+                        self.scan.pos += 1;
+                    }
+                }
+                _ => break,
+            }
+        }
+    }
+
+    fn bare(&mut self) -> String {
+        self.scan
+            .eat_while(|c| c.is_alphanumeric() || c == '_' || c == '-')
+            .to_string()
+    }
+
+    fn key(&mut self) -> Result<(String, Span), Diagnostic> {
+        let start = self.scan.pos;
+        let key = match self.scan.peek() {
+            Some('"') => self
+                .scan
+                .string(ESCAPES, true)
+                .map_err(|e| e.to_diagnostic(Code::ManifestSyntax, &self.scan))?,
+            _ => self.bare(),
+        };
+        if key.is_empty() {
+            self.scan.pos += 1;
+            return Err(self
+                .scan
+                .error(Code::ManifestSyntax, start, "expected a key"));
+        }
+
+        Ok((key, self.scan.span_from(start)))
+    }
+
+    fn value(&mut self) -> Result<(Value, Span), Diagnostic> {
+        let start = self.scan.pos;
+        let value = match self.scan.peek() {
+            Some('"') => Value::Str(
+                self.scan
+                    .string(ESCAPES, true)
+                    .map_err(|e| e.to_diagnostic(Code::ManifestSyntax, &self.scan))?,
+            ),
+            Some('[') => {
+                self.scan.pos += 1;
+                let mut items = Vec::new();
+                loop {
+                    self.skip(true);
+                    if self.scan.peek() == Some(']') {
+                        self.scan.pos += 1;
+                        break;
+                    }
+                    if self.scan.peek().is_none() {
+                        return Err(self.scan.error(Code::ManifestSyntax, start, "unclosed `[`"));
+                    }
+                    items.push(self.value()?);
+                    self.skip(true);
+                    match self.scan.peek() {
+                        Some(',') => self.scan.pos += 1,
+                        Some(']') => {}
+                        _ => {
+                            return Err(self.scan.error(
+                                Code::ManifestSyntax,
+                                self.scan.pos,
+                                "expected `,` or `]`",
+                            ))
+                        }
+                    }
+                }
+                Value::Array(items)
+            }
+            Some('{') => {
+                self.scan.pos += 1;
+                let mut items = Vec::new();
+                loop {
+                    self.skip(false);
+                    if self.scan.peek() == Some('}') {
+                        self.scan.pos += 1;
+                        break;
+                    }
+                    let (key, key_span) = self.key()?;
+                    self.skip(false);
+                    if self.scan.peek() != Some('=') {
+                        return Err(self.scan.error(
+                            Code::ManifestSyntax,
+                            self.scan.pos,
+                            "expected `=`",
+                        ));
+                    }
+                    self.scan.pos += 1;
+                    self.skip(false);
+                    let (value, _) = self.value()?;
+                    items.push((key, value, key_span));
+                    self.skip(false);
+                    match self.scan.peek() {
+                        Some(',') => self.scan.pos += 1,
+                        Some('}') => {}
+                        _ => {
+                            return Err(self.scan.error(
+                                Code::ManifestSyntax,
+                                self.scan.pos,
+                                "expected `,` or `}`",
+                            ))
+                        }
+                    }
+                }
+                Value::Table(items)
+            }
+            _ => {
+                let word = self.bare();
+                match word.as_str() {
+                    "true" => Value::Bool(true),
+                    "false" => Value::Bool(false),
+                    _ => match word.parse::<i64>() {
+                        Ok(n) => Value::Int(n),
+                        Err(_) => {
+                            if word.is_empty() {
+                                self.scan.pos += 1;
+                            }
+                            return Err(self.scan.error(
+                                Code::ManifestSyntax,
+                                start,
+                                "expected a value",
+                            ));
+                        }
+                    },
+                }
+            }
+        };
+
+        Ok((value, self.scan.span_from(start)))
+    }
+
+    /*
+     * Reads every entry; a broken line is reported and skipped so the
+     * rest of the manifest is still checked.
+     */
+    fn entries(&mut self, errors: &mut Vec<Diagnostic>) -> Vec<Entry> {
+        let mut table = Vec::new();
+        let mut out = Vec::new();
+        loop {
+            self.skip(true);
+            if self.scan.peek().is_none() {
+                return out;
+            }
+
+            let result = if self.scan.peek() == Some('[') {
+                let start = self.scan.pos;
+                self.scan.pos += 1;
+                let name = self.table_name();
+                match (name, self.scan.peek()) {
+                    (Ok(name), Some(']')) => {
+                        self.scan.pos += 1;
+                        table = name;
+                        Ok(())
+                    }
+                    (Err(e), _) => Err(e),
+                    _ => Err(self.scan.error(
+                        Code::ManifestSyntax,
+                        start,
+                        "expected `]` after the table name",
+                    )),
+                }
+            } else {
+                self.entry(&table).map(|e| out.push(e))
+            };
+
+            if let Err(e) = result {
+                errors.push(e);
+                while self.scan.peek().is_some_and(|c| c != '\n') {
+                    self.scan.pos += 1;
+                }
+                continue;
+            }
+
+            self.skip(false);
+            if self.scan.peek().is_some_and(|c| c != '\n') {
+                let start = self.scan.pos;
+                while self.scan.peek().is_some_and(|c| c != '\n') {
+                    self.scan.pos += 1;
+                }
+                errors.push(self.scan.error(
+                    Code::ManifestSyntax,
+                    start,
+                    "unexpected text after the entry",
+                ));
+            }
+        }
+    }
+
+    /*
+     * The keys of a table header, `[a.b]` => ["a", "b"].
+     */
+    fn table_name(&mut self) -> Result<Vec<String>, Diagnostic> {
+        let mut name = Vec::new();
+        loop {
+            self.skip(false);
+            name.push(self.key()?.0);
+            self.skip(false);
+            if self.scan.peek() != Some('.') {
+                return Ok(name);
+            }
+            self.scan.pos += 1;
+        }
+    }
+
+    fn entry(&mut self, table: &[String]) -> Result<Entry, Diagnostic> {
+        let (key, key_span) = self.key()?;
+        self.skip(false);
+        if self.scan.peek() != Some('=') {
+            return Err(self.scan.error(
+                Code::ManifestSyntax,
+                self.scan.pos,
+                format!("expected `=` after `{}`", key),
+            ));
+        }
+        self.scan.pos += 1;
+        self.skip(false);
+        let (value, span) = self.value()?;
+
+        Ok(Entry {
+            table: table.to_vec(),
+            key,
+            key_span,
+            value,
+            span,
+        })
+    }
+}
+
+impl Manifest {
+    pub const FILE: &'static str = "neml.toml";
+
+    /*
+     * `path` names the manifest itself or a directory holding one.
+     */
+    pub fn find(path: &Path) -> Option<PathBuf> {
+        if path.is_dir() {
+            let file = path.join(Manifest::FILE);
+            return file.is_file().then_some(file);
+        }
+
+        (path.file_name()? == Manifest::FILE && path.is_file()).then(|| path.to_path_buf())
+    }
+
+    pub fn load(path: &Path) -> std::io::Result<Manifest> {
+        let text = std::fs::read_to_string(path)?;
+
+        Ok(Manifest::parse(
+            &path.to_string_lossy(),
+            &Manifest::root_of(path),
+            &text,
+        ))
+    }
+
+    /*
+     * Stands in for a manifest that could not be read, so the build
+     * reports why instead of compiling nothing.
+     */
+    pub fn unreadable(path: &Path, e: std::io::Error) -> Manifest {
+        let name = path.to_string_lossy();
+        let mut manifest = Manifest::parse(&name, &Manifest::root_of(path), "");
+        let mut d = Diagnostic::error(
+            Code::ManifestUnreadable,
+            format!("cannot read the manifest: {}", e),
+            Span::new(0, 0, 1, 1),
+        );
+        d.file = Some(name.into_owned());
+        manifest.diagnostics = vec![d];
+
+        manifest
+    }
+
+    fn root_of(path: &Path) -> PathBuf {
+        /* a bare `neml.toml` has an empty parent, which names no directory */
+        match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+            _ => PathBuf::from("."),
+        }
+    }
+
+    pub fn parse(name: &str, root: &Path, text: &str) -> Manifest {
+        let mut source = SourceFile::new(name);
+        let mut offset = 0;
+        for line in text.split('\n') {
+            source.push_line(offset, line.trim_end_matches('\r'));
+            offset += line.len() + 1;
+        }
+
+        let mut manifest = Manifest {
+            root: root.to_path_buf(),
+            name: String::new(),
+            entries: Vec::new(),
+            entry_spans: Vec::new(),
+            include: Vec::new(),
+            deps: Vec::new(),
+            indent: None,
+            strict: false,
+            lenient: false,
+            output: None,
+            format: "png".to_string(),
+            diagnostics: Vec::new(),
+            source: source.clone(),
+        };
+
+        let mut cursor = Cursor {
+            scan: Scanner::new(text, Span::new(0, 0, 1, 1)),
+        };
+        let mut errors = Vec::new();
+        let entries = cursor.entries(&mut errors);
+
+        let mut has_name = false;
+        for e in &entries {
+            if let Err(d) = manifest.apply(e) {
+                errors.push(d);
+            }
+            has_name |= e.table == ["project"] && e.key == "name";
+        }
+
+        if manifest.entries.is_empty() && !errors.iter().any(|d| d.code == Code::ManifestType) {
+            errors.push(
+                Diagnostic::error(
+                    Code::ManifestMissing,
+                    "manifest lists no entry documents",
+                    Span::new(0, 0, 1, 1),
+                )
+                .with_help("add `entries = [\"main.neml\"]` under `[project]`"),
+            );
+        }
+
+        if !has_name {
+            errors.push(
+                Diagnostic::error(
+                    Code::ManifestMissing,
+                    "manifest has no project name",
+                    Span::new(0, 0, 1, 1),
+                )
+                .with_help("add `name = \"...\"` under `[project]`"),
+            );
+        }
+
+        for d in &mut errors {
+            d.file = Some(name.to_string());
+        }
+        manifest.diagnostics = errors;
+
+        manifest
+    }
+
+    fn apply(&mut self, e: &Entry) -> Result<(), Diagnostic> {
+        let table: Vec<&str> = e.table.iter().map(String::as_str).collect();
+        match (table.as_slice(), e.key.as_str()) {
+            (["project"], "name") => self.name = Manifest::string(e)?,
+            (["project"], "entries") => {
+                self.entries = self.paths(e)?;
+                if let Value::Array(items) = &e.value {
+                    self.entry_spans = items.iter().map(|(_, span)| *span).collect();
+                }
+            }
+            (["project"], "include") => self.include = self.paths(e)?,
+            (["project"], "indent") => {
+                self.indent = Some(match &e.value {
+                    Value::Int(n) if *n > 0 => IndentStyle::Spaces(*n as usize),
+                    Value::Str(s) if s == "tabs" => IndentStyle::Tabs,
+                    Value::Str(s) if s == "infer" => IndentStyle::Infer,
+                    _ => {
+                        return Err(Manifest::mismatch(
+                            e,
+                            "a positive integer, \"tabs\" or \"infer\"",
+                        ))
+                    }
+                })
+            }
+            (["project"], "strict") => self.strict = Manifest::boolean(e)?,
+            (["project"], "lenient") => self.lenient = Manifest::boolean(e)?,
+            (["dependencies"], name) => {
+                let path = match &e.value {
+                    Value::Str(s) => s.clone(),
+                    Value::Table(items) => match items.iter().find(|(k, _, _)| k == "path") {
+                        Some((_, Value::Str(s), _)) => s.clone(),
+                        _ => return Err(Manifest::mismatch(e, "`{ path = \"...\" }`")),
+                    },
+                    _ => return Err(Manifest::mismatch(e, "`{ path = \"...\" }`")),
+                };
+                self.deps.push(PathDep {
+                    name: name.to_string(),
+                    path: self.root.join(path),
+                });
+            }
+            (["dependencies", name], "path") => self.deps.push(PathDep {
+                name: name.to_string(),
+                path: self.root.join(Manifest::string(e)?),
+            }),
+            (["output"], "dir") => self.output = Some(self.root.join(Manifest::string(e)?)),
+            (["output"], "format") => self.format = Manifest::string(e)?,
+            (table, key) => {
+                let place = match table {
+                    [] => "at the top level".to_string(),
+                    t => format!("in `[{}]`", t.join(".")),
+                };
+                return Err(Diagnostic::warning(
+                    Code::ManifestKey,
+                    format!("unknown key `{}` {}", key, place),
+                    e.key_span,
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn mismatch(e: &Entry, expected: &str) -> Diagnostic {
+        Diagnostic::error(
+            Code::ManifestType,
+            format!(
+                "`{}` should be {}, found {}",
+                e.key,
+                expected,
+                e.value.describe()
+            ),
+            e.span,
+        )
+    }
+
+    fn string(e: &Entry) -> Result<String, Diagnostic> {
+        match &e.value {
+            Value::Str(s) => Ok(s.clone()),
+            _ => Err(Manifest::mismatch(e, "a string")),
+        }
+    }
+
+    fn boolean(e: &Entry) -> Result<bool, Diagnostic> {
+        match &e.value {
+            Value::Bool(b) => Ok(*b),
+            _ => Err(Manifest::mismatch(e, "a boolean")),
+        }
+    }
+
+    fn paths(&self, e: &Entry) -> Result<Vec<PathBuf>, Diagnostic> {
+        let items = match &e.value {
+            Value::Array(items) => items,
+            _ => return Err(Manifest::mismatch(e, "an array of strings")),
+        };
+
+        items
+            .iter()
+            .map(|(v, span)| match v {
+                Value::Str(s) => Ok(self.root.join(s)),
+                v => Err(Diagnostic::error(
+                    Code::ManifestType,
+                    format!("expected a string, found {}", v.describe()),
+                    *span,
+                )),
+            })
+            .collect()
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics.iter().any(|d| d.is_error())
+    }
+
+    /*
+     * `out/<entry path>.<format>` for each entry, with the entry path
+     * taken from the manifest root and stripped of `.neml` only, so
+     * `src/a.v2.neml` renders to `out/src/a.v2.png` and entries with
+     * the same name in different directories do not collide. `..` is
+     * dropped to keep the output inside `dir`, which defaults to the
+     * manifest root so each output lands next to its entry.
+     */
+    pub fn target(&self, entry: &Path) -> Option<PathBuf> {
+        let dir = self.output.as_ref().unwrap_or(&self.root);
+        let relative = entry.strip_prefix(&self.root).unwrap_or(entry);
+        let mut file: PathBuf = relative
+            .components()
+            .filter(|c| matches!(c, Component::Normal(_)))
+            .collect();
+        file.file_name()?;
+        if file.extension().is_some_and(|ext| ext == "neml") {
+            file.set_extension("");
+        }
+
+        let mut target = dir.join(file).into_os_string();
+        target.push(".");
+        target.push(&self.format);
+
+        Some(PathBuf::from(target))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(text: &str) -> Manifest {
+        Manifest::parse("neml.toml", Path::new("proj"), text)
+    }
+
+    const PROJECT: &str = "[project]\nname = \"p\"\nentries = [\"a.neml\"]\n";
+
+    #[test]
+    fn target_keeps_inner_dots() {
+        let m = manifest(&format!("{}[output]\ndir = \"out\"\n", PROJECT));
+        assert_eq!(
+            m.target(Path::new("proj/a.v2.neml")),
+            Some(PathBuf::from("proj/out/a.v2.png"))
+        );
+    }
+
+    #[test]
+    fn target_keeps_the_entry_directory() {
+        let m = manifest(&format!(
+            "{}[output]\ndir = \"out\"\nformat = \"svg\"\n",
+            PROJECT
+        ));
+        assert_eq!(
+            m.target(Path::new("proj/src/main.neml")),
+            Some(PathBuf::from("proj/out/src/main.svg"))
+        );
+        assert_eq!(
+            m.target(Path::new("proj/docs/main.neml")),
+            Some(PathBuf::from("proj/out/docs/main.svg"))
+        );
+    }
+
+    #[test]
+    fn target_stays_inside_the_output_dir() {
+        let m = manifest(&format!("{}[output]\ndir = \"out\"\n", PROJECT));
+        assert_eq!(
+            m.target(Path::new("proj/../shared/x.neml")),
+            Some(PathBuf::from("proj/out/shared/x.png"))
+        );
+    }
+
+    #[test]
+    fn dotted_table_headers() {
+        let m = manifest(&format!(
+            "{}[dependencies]\nshapes = {{ path = \"../shapes\" }}\n\n\
+             [dependencies.icons]\npath = \"../icons\"\n\n\
+             [ dependencies . \"my lib\" ]\npath = \"lib\"\n",
+            PROJECT
+        ));
+        assert!(m.diagnostics.is_empty(), "{:?}", m.diagnostics);
+        let deps: Vec<_> = m
+            .deps
+            .iter()
+            .map(|d| (d.name.as_str(), d.path.clone()))
+            .collect();
+        assert_eq!(
+            deps,
+            vec![
+                ("shapes", PathBuf::from("proj/../shapes")),
+                ("icons", PathBuf::from("proj/../icons")),
+                ("my lib", PathBuf::from("proj/lib")),
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_keys_name_the_dotted_table() {
+        let m = manifest(&format!(
+            "{}[dependencies.icons]\nversion = \"1\"\n",
+            PROJECT
+        ));
+        assert_eq!(m.diagnostics.len(), 1);
+        assert_eq!(m.diagnostics[0].code, Code::ManifestKey);
+        assert_eq!(
+            m.diagnostics[0].message,
+            "unknown key `version` in `[dependencies.icons]`"
+        );
+    }
+
+    #[test]
+    fn target_defaults_to_the_manifest_root() {
+        assert_eq!(
+            manifest(PROJECT).target(Path::new("proj/src/a.neml")),
+            Some(PathBuf::from("proj/src/a.png"))
+        );
+    }
+}
@@ -1,7 +1,21 @@
+pub mod attr;
 pub mod content;
+pub mod edge;
 pub mod engine;
 pub mod import;
+pub mod indent;
+pub mod manifest;
+pub mod modpath;
+pub mod module;
+pub mod resolve;
 pub mod segment;
 
 pub mod endpoint;
+pub use attr::{AttrValue, Attrs};
+pub use edge::{Edge, EdgeKind};
 pub use endpoint::NeiEndpoint;
+pub use indent::{IndentStyle, Indentation};
+pub use manifest::{Manifest, PathDep};
+pub use modpath::ModulePath;
+pub use module::{ImportEdge, ImportGraph, Lookup, Module, ModuleCache, Symbol};
+pub use resolve::{Resolver, Target};
@@ -0,0 +1,211 @@
+use crate::diag::{Code, Diagnostic};
+use crate::parse::Span;
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+
+/*
+ * The module path of a `use` declaration, in one of three forms:
+ *
+ *   ./a/b  ../a/b   relative to the importing file
+ *   /a/b            relative to the project root
+ *   a/b             a library, see `Engine::search_roots`
+ *
+ * Segments are normalized: `.` is dropped and `..` only remains at the
+ * front of a relative path.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModulePath {
+    Relative(Vec<String>),
+    Root(Vec<String>),
+    Library(Vec<String>),
+}
+
+impl ModulePath {
+    pub fn parse(path: &str, span: Span) -> Result<ModulePath, Diagnostic> {
+        let rooted = path.starts_with('/');
+        let raw: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+        let relative = !rooted && matches!(raw.first(), Some(&".") | Some(&".."));
+
+        let mut segments: Vec<String> = Vec::new();
+        for segment in raw {
+            match segment {
+                "." => {}
+                ".." => match segments.last() {
+                    Some(last) if last != ".." => {
+                        segments.pop();
+                    }
+                    _ if relative => segments.push("..".to_string()),
+                    _ => {
+                        let (from, help) = match rooted {
+                            true => ("the project root", "remove the leading `..`"),
+                            false => (
+                                "its first segment",
+                                "start the path with `../` to import relative to this file",
+                            ),
+                        };
+                        return Err(Diagnostic::error(
+                            Code::PathEscapesRoot,
+                            format!("`{}` climbs above {}", path, from),
+                            span,
+                        )
+                        .with_help(help));
+                    }
+                },
+                s => segments.push(s.to_string()),
+            }
+        }
+
+        if matches!(segments.last().map(String::as_str), None | Some("..")) {
+            return Err(Diagnostic::error(
+                Code::InvalidModulePath,
+                format!("`{}` does not name a module", path),
+                span,
+            )
+            .with_help("end the path with a file name, e.g. `./shapes`"));
+        }
+
+        Ok(match (rooted, relative) {
+            (true, _) => ModulePath::Root(segments),
+            (false, true) => ModulePath::Relative(segments),
+            (false, false) => ModulePath::Library(segments),
+        })
+    }
+
+    pub fn segments(&self) -> &[String] {
+        match self {
+            ModulePath::Relative(s) | ModulePath::Root(s) | ModulePath::Library(s) => s,
+        }
+    }
+
+    /*
+     * The file the path names, relative to wherever its form is
+     * resolved from: `../a/b` => `../a/b.neml`.
+     */
+    pub fn file(&self) -> PathBuf {
+        let segments = self.segments();
+        let mut file: PathBuf = segments[..segments.len() - 1].iter().collect();
+        file.push(format!("{}.neml", segments[segments.len() - 1]));
+
+        file
+    }
+
+    /*
+     * `a/b/../c` => `a/c` without touching the file system, so it also
+     * works for files that do not exist.
+     */
+    pub fn normalize(path: &Path) -> PathBuf {
+        let mut out = PathBuf::new();
+        for component in path.components() {
+            match component {
+                Component::CurDir => {}
+                Component::ParentDir => {
+                    if !out.pop() {
+                        out.push("..");
+                    }
+                }
+                c => out.push(c),
+            }
+        }
+
+        out
+    }
+}
+
+impl fmt::Display for ModulePath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let joined = self.segments().join("/");
+        match self {
+            ModulePath::Relative(s) if s[0] == ".." => write!(f, "{}", joined),
+            ModulePath::Relative(_) => write!(f, "./{}", joined),
+            ModulePath::Root(_) => write!(f, "/{}", joined),
+            ModulePath::Library(_) => write!(f, "{}", joined),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(path: &str) -> Result<ModulePath, Code> {
+        ModulePath::parse(path, Span::default()).map_err(|d| d.code)
+    }
+
+    fn segments(s: &[&str]) -> Vec<String> {
+        s.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn sibling_is_relative() {
+        assert_eq!(parse("./x"), Ok(ModulePath::Relative(segments(&["x"]))));
+        assert_eq!(parse("./x").unwrap().file(), PathBuf::from("x.neml"));
+    }
+
+    #[test]
+    fn parent_is_relative() {
+        let p = parse("../x").unwrap();
+        assert_eq!(p, ModulePath::Relative(segments(&["..", "x"])));
+        assert_eq!(p.file(), PathBuf::from("../x.neml"));
+        assert_eq!(p.to_string(), "../x");
+    }
+
+    #[test]
+    fn leading_slash_is_root() {
+        let p = parse("/x").unwrap();
+        assert_eq!(p, ModulePath::Root(segments(&["x"])));
+        assert_eq!(p.to_string(), "/x");
+    }
+
+    #[test]
+    fn bare_path_is_library() {
+        let p = parse("lib/x").unwrap();
+        assert_eq!(p, ModulePath::Library(segments(&["lib", "x"])));
+        assert_eq!(p.file(), PathBuf::from("lib/x.neml"));
+    }
+
+    #[test]
+    fn dots_are_normalized() {
+        assert_eq!(parse("a/../b"), Ok(ModulePath::Library(segments(&["b"]))));
+        assert_eq!(
+            parse("a/./b"),
+            Ok(ModulePath::Library(segments(&["a", "b"])))
+        );
+        assert_eq!(
+            parse("./a/../../b"),
+            Ok(ModulePath::Relative(segments(&["..", "b"])))
+        );
+        assert_eq!(
+            parse("/src/./x"),
+            Ok(ModulePath::Root(segments(&["src", "x"])))
+        );
+    }
+
+    #[test]
+    fn climbing_above_the_root_is_rejected() {
+        assert_eq!(parse("/../x"), Err(Code::PathEscapesRoot));
+    }
+
+    #[test]
+    fn climbing_above_a_library_is_rejected() {
+        assert_eq!(parse("a/../../b"), Err(Code::PathEscapesRoot));
+    }
+
+    #[test]
+    fn path_must_name_a_file() {
+        assert_eq!(parse("a/.."), Err(Code::InvalidModulePath));
+        assert_eq!(parse("../.."), Err(Code::InvalidModulePath));
+        assert_eq!(parse("./"), Err(Code::InvalidModulePath));
+    }
+
+    #[test]
+    fn normalize_is_lexical() {
+        assert_eq!(
+            ModulePath::normalize(Path::new("/p/src/./sub/../x.neml")),
+            PathBuf::from("/p/src/x.neml")
+        );
+        assert_eq!(
+            ModulePath::normalize(Path::new("a/../../b")),
+            PathBuf::from("../b")
+        );
+    }
+}
@@ -0,0 +1,231 @@
+use super::content::Item;
+use super::edge::Edge;
+use crate::diag::{Diagnostic, SourceFile};
+use crate::parse::{Document, Span};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::Hasher;
+use std::path::{Path, PathBuf};
+
+/*
+ * `diagnostics` and `source` belong to this file only; those of the
+ * modules it imports stay with them and are reached through `deps`.
+ */
+#[derive(Debug)]
+pub struct Module {
+    pub name: String,
+    pub path: PathBuf,
+    pub hash: u64,
+    pub document: Document,
+    pub items: Vec<Item>,
+    pub edges: Vec<Edge>,
+    pub symbols: Vec<Symbol>,
+    pub deps: Vec<usize>,
+    pub diagnostics: Vec<Diagnostic>,
+    pub source: Option<SourceFile>,
+}
+
+/*
+ * Compiled modules shared by every `Engine` built with the same cache,
+ * keyed by canonical path. Indices are stable: a module that changed on
+ * disk is recompiled into its old slot. Entries are compiled with the
+ * `Config` of whichever document loaded them first.
+ */
+#[derive(Debug, Default)]
+pub struct ModuleCache {
+    pub modules: Vec<Module>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lookup {
+    Found { module: usize, index: usize },
+    Private,
+    Missing,
+}
+
+/*
+ * A name brought into scope by a `use` declaration: `index` is the
+ * node inside `modules[module]` the name stands for.
+ */
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    pub name: String,
+    pub module: usize,
+    pub index: usize,
+    pub glob: bool,
+    pub public: bool,
+    pub span: Span,
+}
+
+impl Module {
+    /*
+     * Walks an anchor such as `c/d` down the module's node tree: `c`
+     * must be a top-level node and `d` one of its children.
+     */
+    pub fn find(&self, anchor: &str) -> Option<usize> {
+        Module::find_in(&self.items, 0, anchor)
+    }
+
+    pub fn find_in(items: &[Item], from: usize, anchor: &str) -> Option<usize> {
+        let mut current = from;
+        for segment in anchor.split('/') {
+            current = items
+                .iter()
+                .enumerate()
+                .skip(1)
+                .find(|(_, item)| item.root == current && item.name.as_deref() == Some(segment))
+                .map(|(i, _)| i)?;
+        }
+
+        Some(current)
+    }
+
+    /*
+     * What an importer may see of `modules[module]`: nodes marked `pub`,
+     * and the names it re-exports with `pub use`. Only the imported node
+     * itself has to be `pub`, not the nodes on the way to it.
+     */
+    pub fn lookup(modules: &[Module], module: usize, anchor: &str) -> Lookup {
+        let m = &modules[module];
+        if let Some(index) = m.find(anchor) {
+            return match m.items[index].public {
+                true => Lookup::Found { module, index },
+                false => Lookup::Private,
+            };
+        }
+
+        let (head, rest) = match anchor.split_once('/') {
+            Some((head, rest)) => (head, Some(rest)),
+            None => (anchor, None),
+        };
+
+        let sym = match m.symbols.iter().find(|s| s.name == head) {
+            Some(sym) if sym.public => sym,
+            Some(_) => return Lookup::Private,
+            None => return Lookup::Missing,
+        };
+
+        let index = match rest {
+            None => {
+                return Lookup::Found {
+                    module: sym.module,
+                    index: sym.index,
+                }
+            }
+            Some(rest) => match Module::find_in(&modules[sym.module].items, sym.index, rest) {
+                Some(index) => index,
+                None => return Lookup::Missing,
+            },
+        };
+
+        match modules[sym.module].items[index].public {
+            true => Lookup::Found {
+                module: sym.module,
+                index,
+            },
+            false => Lookup::Private,
+        }
+    }
+
+    pub fn item(&self, index: usize) -> Option<&Item> {
+        self.items.get(index)
+    }
+}
+
+impl ModuleCache {
+    pub fn new() -> Self {
+        ModuleCache {
+            modules: Vec::new(),
+        }
+    }
+
+    pub fn hash(bytes: &[u8]) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        hasher.write(bytes);
+        hasher.finish()
+    }
+
+    pub fn position(&self, path: &Path) -> Option<usize> {
+        self.modules.iter().position(|m| m.path == path)
+    }
+
+    /*
+     * A module is fresh while its file hashes the same as when it was
+     * compiled and every module it imports is fresh too.
+     */
+    pub fn is_fresh(&self, index: usize, seen: &mut Vec<usize>) -> bool {
+        if seen.contains(&index) {
+            return true;
+        }
+        seen.push(index);
+
+        let m = &self.modules[index];
+        match std::fs::read(&m.path) {
+            Ok(bytes) if ModuleCache::hash(&bytes) == m.hash => {}
+            _ => return false,
+        }
+
+        m.deps.iter().all(|&d| self.is_fresh(d, seen))
+    }
+
+    pub fn insert(&mut self, module: Module) -> usize {
+        match self.position(&module.path) {
+            Some(index) => {
+                self.modules[index] = module;
+                index
+            }
+            None => {
+                self.modules.push(module);
+                self.modules.len() - 1
+            }
+        }
+    }
+}
+
+/*
+ * One `use` declaration followed from `from` to `to`. `line` keeps the
+ * declaration's source text so a cycle can be reported without going
+ * back to the file.
+ */
+#[derive(Debug, Clone)]
+pub struct ImportEdge {
+    pub from: PathBuf,
+    pub to: PathBuf,
+    pub file: String,
+    pub line: String,
+    pub span: Span,
+}
+
+#[derive(Debug, Default)]
+pub struct ImportGraph {
+    pub edges: Vec<ImportEdge>,
+}
+
+impl ImportGraph {
+    pub fn new() -> Self {
+        ImportGraph { edges: Vec::new() }
+    }
+
+    pub fn add(&mut self, edge: ImportEdge) {
+        self.edges.push(edge);
+    }
+
+    pub fn edge(&self, from: &Path, to: &Path) -> Option<&ImportEdge> {
+        self.edges
+            .iter()
+            .rev()
+            .find(|e| e.from == from && e.to == to)
+    }
+
+    /*
+     * `stack` is the chain of files being loaded, outermost first. If
+     * `to` is already on it, returns the edges that lead from `to` back
+     * to itself, in order.
+     */
+    pub fn cycle(&self, stack: &[PathBuf], to: &Path) -> Option<Vec<&ImportEdge>> {
+        let start = stack.iter().position(|p| p == to)?;
+        let mut chain: Vec<&Path> = stack[start..].iter().map(|p| p.as_path()).collect();
+        chain.push(to);
+
+        chain.windows(2).map(|w| self.edge(w[0], w[1])).collect()
+    }
+}
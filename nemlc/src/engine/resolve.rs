@@ -0,0 +1,247 @@
+use super::content::Item;
+use super::module::{Module, Symbol};
+use crate::diag::{Code, Diagnostic};
+use crate::parse::Span;
+
+/*
+ * What a name in #[content] stands for: a node of this document, or
+ * node `index` of the module at `module` in the module cache, see
+ * `Compiled::imported`.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    Local(usize),
+    Import { module: usize, index: usize },
+}
+
+pub struct Resolver<'a> {
+    pub items: &'a [Item],
+    pub symbols: &'a [Symbol],
+    pub modules: &'a [Module],
+}
+
+impl<'a> Resolver<'a> {
+    pub fn new(items: &'a [Item], symbols: &'a [Symbol], modules: &'a [Module]) -> Self {
+        Resolver {
+            items,
+            symbols,
+            modules,
+        }
+    }
+
+    /*
+     * Imported names come first, so an alias shadows a local node of
+     * the same name. A plain name is otherwise looked up anywhere in the
+     * local tree; `a/b` walks down from the top level, or from an
+     * imported anchor when `a` is an imported name. `source` is the node
+     * the relation starts from, never offered as a spelling hint.
+     */
+    pub fn resolve(&self, name: &str, span: Span, source: usize) -> Result<Target, Diagnostic> {
+        let (head, rest) = match name.split_once('/') {
+            Some((head, rest)) => (head, Some(rest)),
+            None => (name, None),
+        };
+
+        let symbols: Vec<usize> = (0..self.symbols.len())
+            .filter(|&i| self.symbols[i].name == head)
+            .collect();
+
+        if let Some(&first) = symbols.first() {
+            let sym = &self.symbols[first];
+            if symbols.iter().any(|&i| {
+                self.symbols[i].module != sym.module || self.symbols[i].index != sym.index
+            }) {
+                let mut d = Diagnostic::error(
+                    Code::AmbiguousName,
+                    format!("`{}` is imported more than once", head),
+                    span,
+                );
+                for &i in &symbols {
+                    d = d.with_label(self.symbols[i].span, "imported here");
+                }
+                return Err(d.with_help("give one of the imports a different name with `as`"));
+            }
+
+            let index = match rest {
+                Some(rest) => Module::find_in(&self.modules[sym.module].items, sym.index, rest)
+                    .ok_or_else(|| self.unknown(name, span, source))?,
+                None => sym.index,
+            };
+
+            return Ok(Target::Import {
+                module: sym.module,
+                index,
+            });
+        }
+
+        if rest.is_some() {
+            return Module::find_in(self.items, 0, name)
+                .map(Target::Local)
+                .ok_or_else(|| self.unknown(name, span, source));
+        }
+
+        let found: Vec<usize> = (1..self.items.len())
+            .filter(|&i| self.items[i].name.as_deref() == Some(name))
+            .collect();
+
+        match found.as_slice() {
+            [] => Err(self.unknown(name, span, source)),
+            [index] => Ok(Target::Local(*index)),
+            _ => {
+                let mut d = Diagnostic::error(
+                    Code::AmbiguousName,
+                    format!("`{}` names {} nodes", name, found.len()),
+                    span,
+                );
+                for &i in &found {
+                    d = d.with_label(self.items[i].name_span, "could be this node");
+                }
+                let path = self.path(found[0]);
+                if found.iter().all(|&i| self.items[i].path == path) {
+                    return Err(d.with_help(format!(
+                        "they share the path `{}`; rename one of them or give it an `[id=...]`",
+                        path
+                    )));
+                }
+                Err(d.with_help(format!(
+                    "write the path from the top level, e.g. `{}`",
+                    path
+                )))
+            }
+        }
+    }
+
+    pub fn unknown(&self, name: &str, span: Span, source: usize) -> Diagnostic {
+        let d = Diagnostic::error(Code::UnknownName, format!("cannot find `{}`", name), span);
+        match self.similar(name, source) {
+            Some(close) => d.with_help(format!("a node named `{}` exists", close)),
+            None => d.with_help("define it in #[content] or import it with `use`"),
+        }
+    }
+
+    /*
+     * The local or imported name closest to the last segment of `name`,
+     * if it is only a typo away: fewer edits than the name is long, so
+     * `x` never suggests `z`.
+     */
+    pub fn similar(&self, name: &str, source: usize) -> Option<String> {
+        let last = name.rsplit('/').next().unwrap_or(name);
+        let len = last.chars().count();
+        let limit = (len / 3).max(1);
+
+        self.items
+            .iter()
+            .enumerate()
+            .skip(1)
+            .filter(|&(i, _)| i != source)
+            .filter_map(|(_, item)| item.name.as_deref())
+            .chain(self.symbols.iter().map(|s| s.name.as_str()))
+            .filter(|&n| n != last)
+            .map(|n| (Resolver::distance(last, n), n))
+            .filter(|&(d, _)| d <= limit && d < len)
+            .min_by_key(|&(d, _)| d)
+            .map(|(_, n)| n.to_string())
+    }
+
+    fn distance(a: &str, b: &str) -> usize {
+        let b: Vec<char> = b.chars().collect();
+        let mut row: Vec<usize> = (0..=b.len()).collect();
+        for (i, ca) in a.chars().enumerate() {
+            let mut prev = row[0];
+            row[0] = i + 1;
+            for j in 0..b.len() {
+                let cost = if ca == b[j] { prev } else { prev + 1 };
+                prev = row[j + 1];
+                row[j + 1] = cost.min(row[j] + 1).min(prev + 1);
+            }
+        }
+
+        row[b.len()]
+    }
+
+    pub fn path(&self, index: usize) -> String {
+        self.items[index].path.clone()
+    }
+
+    /*
+     * Warns once per imported name that hides a node of this document.
+     */
+    pub fn shadowed(&self) -> Vec<Diagnostic> {
+        let mut out = Vec::new();
+        for sym in self.symbols {
+            let locals: Vec<&Item> = self
+                .items
+                .iter()
+                .skip(1)
+                .filter(|item| item.name.as_deref() == Some(sym.name.as_str()))
+                .collect();
+
+            if locals.is_empty() {
+                continue;
+            }
+
+            let mut d = Diagnostic::warning(
+                Code::ShadowedName,
+                format!("imported name `{}` shadows a local node", sym.name),
+                sym.span,
+            );
+            for item in locals {
+                d = d.with_label(item.name_span, "this node is shadowed");
+            }
+            out.push(d.with_help("rename the import with `as`"));
+        }
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diag::Code;
+    use crate::engine::engine::{Compiled, Engine};
+
+    fn compile(source: &str) -> Compiled {
+        Engine::init().compile_str(Engine::init_config("test.neml".to_string()), source)
+    }
+
+    fn help(out: &Compiled, code: Code) -> String {
+        let d = out.diagnostics.iter().find(|d| d.code == code).unwrap();
+        d.help.clone().unwrap_or_default()
+    }
+
+    #[test]
+    fn typos_are_suggested() {
+        let out = compile("#[content]\nserver\na + sever\n");
+        assert_eq!(
+            help(&out, Code::UnknownName),
+            "a node named `server` exists"
+        );
+    }
+
+    #[test]
+    fn a_one_letter_name_is_not_a_typo() {
+        let out = compile("#[content]\nz + x\n");
+        assert!(!help(&out, Code::UnknownName).contains("exists"));
+    }
+
+    #[test]
+    fn the_source_node_is_not_suggested() {
+        let out = compile("#[content]\nnodes + node\n");
+        assert!(!help(&out, Code::UnknownName).contains("exists"));
+    }
+
+    #[test]
+    fn ambiguous_names_with_distinct_paths() {
+        let out = compile("#[content]\na\n  x\nb\n  x\nc + x\n");
+        assert_eq!(
+            help(&out, Code::AmbiguousName),
+            "write the path from the top level, e.g. `a/x`"
+        );
+    }
+
+    #[test]
+    fn ambiguous_names_sharing_a_path() {
+        let out = compile("#[content]\nx\nx\nc + x\n");
+        assert!(help(&out, Code::AmbiguousName).contains("`[id=...]`"));
+    }
+}
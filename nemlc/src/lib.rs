@@ -1,3 +1,4 @@
+pub mod diag;
 pub mod engine;
 pub mod parse;
 pub mod utils;
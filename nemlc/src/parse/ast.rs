@@ -0,0 +1,158 @@
+use super::span::Span;
+
+#[derive(Debug, Clone, Default)]
+pub struct Document {
+    pub imports: Vec<ImportSection>,
+    pub contents: Vec<ContentSection>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportSection {
+    pub header: Span,
+    pub uses: Vec<UseDecl>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct UseDecl {
+    pub public: bool,
+    pub tree: UseTree,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PathSep {
+    Slash,
+    Dot,
+}
+
+#[derive(Debug, Clone)]
+pub enum UseTree {
+    /* `name/...` or `name....`: one segment, then the rest of the tree */
+    Path {
+        name: String,
+        name_span: Span,
+        sep: PathSep,
+        next: Box<UseTree>,
+        span: Span,
+    },
+    /* the final anchor of a path, optionally renamed with `as` */
+    Name {
+        name: String,
+        alias: Option<String>,
+        span: Span,
+    },
+    /* `{a, b, ...}` */
+    Group {
+        items: Vec<UseTree>,
+        span: Span,
+    },
+    /* `*`: every anchor below the path so far */
+    Glob {
+        span: Span,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct ContentSection {
+    pub header: Span,
+    pub nodes: Vec<Node>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct Node {
+    pub level: usize,
+    pub name: String,
+    pub name_span: Span,
+    pub public: bool,
+    pub attribute: Option<Attribute>,
+    pub relation: Option<Relation>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RelationOp {
+    Plus,
+    Minus,
+    Star,
+}
+
+#[derive(Debug, Clone)]
+pub struct Relation {
+    pub op: RelationOp,
+    pub op_span: Span,
+    pub target: String,
+    pub target_span: Span,
+    pub label: Option<Attribute>,
+    pub attribute: Option<Attribute>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct Attribute {
+    pub text: String,
+    pub span: Span,
+}
+
+impl Document {
+    pub fn nodes(&self) -> impl Iterator<Item = &Node> {
+        self.contents.iter().flat_map(|c| c.nodes.iter())
+    }
+
+    pub fn uses(&self) -> impl Iterator<Item = &UseDecl> {
+        self.imports.iter().flat_map(|i| i.uses.iter())
+    }
+}
+
+impl ImportSection {
+    pub fn new(header: Span) -> Self {
+        ImportSection {
+            header,
+            uses: Vec::new(),
+            span: header,
+        }
+    }
+
+    pub fn push(&mut self, decl: UseDecl) {
+        self.span = self.span.to(decl.span);
+        self.uses.push(decl);
+    }
+}
+
+impl ContentSection {
+    pub fn new(header: Span) -> Self {
+        ContentSection {
+            header,
+            nodes: Vec::new(),
+            span: header,
+        }
+    }
+
+    pub fn push(&mut self, node: Node) {
+        self.span = self.span.to(node.span);
+        self.nodes.push(node);
+    }
+}
+
+impl UseTree {
+    pub fn span(&self) -> Span {
+        match self {
+            UseTree::Path { span, .. }
+            | UseTree::Name { span, .. }
+            | UseTree::Group { span, .. }
+            | UseTree::Glob { span } => *span,
+        }
+    }
+}
+
+impl RelationOp {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RelationOp::Plus => "+",
+            RelationOp::Minus => "-",
+            RelationOp::Star => "*",
+        }
+    }
+}
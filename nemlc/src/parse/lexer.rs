@@ -0,0 +1,296 @@
+use super::scanner::Scanner;
+use super::span::Span;
+use super::token::{Token, TokenKind};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LexMode {
+    Import,
+    Content,
+}
+
+pub struct Lexer {
+    pub mode: LexMode,
+}
+
+impl Lexer {
+    pub fn new(mode: LexMode) -> Self {
+        Lexer { mode }
+    }
+
+    pub fn tokenize(&self, source: &str) -> Vec<Token> {
+        let mut out_data = Vec::new();
+        let mut offset = 0;
+        for (i, line) in source.split_inclusive('\n').enumerate() {
+            let text = line.trim_end_matches(['\n', '\r']);
+            out_data.extend(self.tokenize_line(text, i + 1, offset));
+            offset += line.len();
+        }
+
+        out_data
+    }
+
+    pub fn tokenize_line(&self, line: &str, line_number: usize, offset: usize) -> Vec<Token> {
+        let mut out_data = Vec::new();
+        let mut cur = Scanner::new(line, Span::new(offset, offset, line_number, 1));
+
+        while let Some(c) = cur.peek() {
+            if c == ' ' || c == '\t' {
+                cur.pos += 1;
+            } else {
+                break;
+            }
+        }
+
+        if cur.pos > 0 {
+            let text = cur.text_from(0).to_string();
+            out_data.push(Token::new(TokenKind::Indent(text), cur.span_from(0)));
+        }
+
+        if let Some(section) = Lexer::section(line.trim()) {
+            let start = cur.pos;
+            cur.pos = cur
+                .chars
+                .iter()
+                .rposition(|&(_, c)| !c.is_whitespace())
+                .map_or(start, |i| i + 1);
+            out_data.push(Token::new(
+                TokenKind::Section(section),
+                cur.span_from(start),
+            ));
+
+            return out_data;
+        }
+
+        while let Some(c) = cur.peek() {
+            let start = cur.pos;
+
+            if c.is_whitespace() {
+                cur.pos += 1;
+                continue;
+            }
+
+            if c == '/' && cur.peek_at(1) == Some('/') {
+                cur.pos = cur.chars.len();
+                let text = cur.text_from(start)[2..].trim().to_string();
+                out_data.push(Token::new(TokenKind::Comment(text), cur.span_from(start)));
+                break;
+            }
+
+            if c == '"' {
+                out_data.push(self.quoted(&mut cur));
+                continue;
+            }
+
+            let kind = match self.mode {
+                LexMode::Import => self.import_token(&mut cur, c),
+                LexMode::Content => self.content_token(&mut cur),
+            };
+            out_data.push(Token::new(kind, cur.span_from(start)));
+        }
+
+        out_data
+    }
+
+    fn section(s: &str) -> Option<String> {
+        let name = s.strip_prefix("#[")?.strip_suffix(']')?;
+        Some(name.trim().to_string())
+    }
+
+    fn quoted(&self, cur: &mut Scanner) -> Token {
+        let start = cur.pos;
+        let kind = match cur.string(&[('"', '"'), ('\\', '\\')], false) {
+            Ok(text) => TokenKind::Str(text),
+            Err(_) => TokenKind::Invalid(cur.text_from(start).to_string()),
+        };
+
+        Token::new(kind, cur.span_from(start))
+    }
+
+    fn is_import_punct(c: char) -> bool {
+        matches!(c, '/' | '.' | '{' | '}' | ',' | '*' | '"')
+    }
+
+    fn import_token(&self, cur: &mut Scanner, c: char) -> TokenKind {
+        let punct = match c {
+            '/' => Some(TokenKind::Slash),
+            '.' => Some(TokenKind::Dot),
+            '{' => Some(TokenKind::LBrace),
+            '}' => Some(TokenKind::RBrace),
+            ',' => Some(TokenKind::Comma),
+            '*' => Some(TokenKind::Star),
+            _ => None,
+        };
+
+        if let Some(kind) = punct {
+            cur.pos += 1;
+            return kind;
+        }
+
+        match cur.eat_while(|c| !c.is_whitespace() && !Lexer::is_import_punct(c)) {
+            "pub" => TokenKind::Pub,
+            "use" => TokenKind::Use,
+            "as" => TokenKind::As,
+            word => TokenKind::Ident(word.to_string()),
+        }
+    }
+
+    /*
+     * A word is a relation operator only when it is exactly `+`, `-` or
+     * `*`. A backslash makes the next character part of the word, so
+     * `\-` is a node named `-` and `a\ b` one named `a b`.
+     */
+    fn content_token(&self, cur: &mut Scanner) -> TokenKind {
+        let mut word = String::new();
+        let mut escaped = false;
+        while let Some(c) = cur.peek() {
+            if c.is_whitespace() {
+                break;
+            }
+            cur.pos += 1;
+
+            if c == '\\' {
+                if let Some(next) = cur.peek() {
+                    word.push(next);
+                    cur.pos += 1;
+                    escaped = true;
+                    continue;
+                }
+            }
+            word.push(c);
+        }
+
+        match word.as_str() {
+            "+" if !escaped => TokenKind::Plus,
+            "-" if !escaped => TokenKind::Minus,
+            "*" if !escaped => TokenKind::Star,
+            _ => TokenKind::Ident(word),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spans(line: &str) -> Vec<(usize, usize, usize)> {
+        Lexer::new(LexMode::Content)
+            .tokenize_line(line, 1, 0)
+            .iter()
+            .map(|t| (t.span.start, t.span.end, t.span.column))
+            .collect()
+    }
+
+    #[test]
+    fn cjk_spans_are_bytes_with_char_columns() {
+        assert_eq!(
+            spans("服务 + 数据库"),
+            vec![(0, 6, 1), (7, 8, 4), (9, 18, 6)]
+        );
+    }
+
+    #[test]
+    fn accented_spans() {
+        assert_eq!(
+            spans("café - naïve"),
+            vec![(0, 5, 1), (6, 7, 6), (8, 14, 8)]
+        );
+    }
+
+    #[test]
+    fn emoji_spans() {
+        let tokens = Lexer::new(LexMode::Content).tokenize_line("  🚀 * \"ö\"", 3, 10);
+        let got: Vec<_> = tokens
+            .iter()
+            .map(|t| (t.span.start, t.span.end, t.span.column))
+            .collect();
+        assert_eq!(
+            got,
+            vec![(10, 12, 1), (12, 16, 3), (17, 18, 5), (19, 23, 7)]
+        );
+        assert_eq!(tokens[3].kind, TokenKind::Str("ö".to_string()));
+        assert!(tokens.iter().all(|t| t.span.line == 3));
+    }
+
+    #[test]
+    fn spans_on_later_lines_keep_their_offset() {
+        let tokens = Lexer::new(LexMode::Content).tokenize("é\n服 + x\n");
+        assert_eq!(tokens[1].span, Span::new(3, 6, 2, 1));
+        assert_eq!(tokens[3].span, Span::new(9, 10, 2, 5));
+    }
+
+    fn kinds(line: &str) -> Vec<TokenKind> {
+        Lexer::new(LexMode::Content)
+            .tokenize_line(line, 1, 0)
+            .into_iter()
+            .map(|t| t.kind)
+            .collect()
+    }
+
+    fn ident(s: &str) -> TokenKind {
+        TokenKind::Ident(s.to_string())
+    }
+
+    #[test]
+    fn hyphen_inside_a_word_is_not_an_operator() {
+        assert_eq!(kinds("front-end"), vec![ident("front-end")]);
+    }
+
+    #[test]
+    fn pluses_inside_a_word_are_not_operators() {
+        assert_eq!(kinds("C++"), vec![ident("C++")]);
+    }
+
+    #[test]
+    fn quoted_names_keep_their_operators() {
+        assert_eq!(
+            kinds("\"a - b\""),
+            vec![TokenKind::Str("a - b".to_string())]
+        );
+    }
+
+    #[test]
+    fn escapes_make_words() {
+        assert_eq!(kinds("\\-"), vec![ident("-")]);
+        assert_eq!(kinds("a\\ b"), vec![ident("a b")]);
+        assert_eq!(
+            kinds("\\+ - \\*"),
+            vec![ident("+"), TokenKind::Minus, ident("*")]
+        );
+    }
+
+    #[test]
+    fn escaped_brackets_keep_their_source_span() {
+        let tokens = Lexer::new(LexMode::Content).tokenize_line("a\\[ + b", 1, 0);
+        assert_eq!(tokens[0].kind, ident("a["));
+        assert_eq!((tokens[0].span.start, tokens[0].span.end), (0, 3));
+    }
+
+    #[test]
+    fn standalone_operators() {
+        assert_eq!(
+            kinds("a + b"),
+            vec![ident("a"), TokenKind::Plus, ident("b")]
+        );
+        assert_eq!(
+            kinds("a - b"),
+            vec![ident("a"), TokenKind::Minus, ident("b")]
+        );
+        assert_eq!(
+            kinds("a * b"),
+            vec![ident("a"), TokenKind::Star, ident("b")]
+        );
+    }
+
+    #[test]
+    fn operators_inside_attributes_are_still_tokens() {
+        assert_eq!(
+            kinds("x [tags=(a - b)]"),
+            vec![
+                ident("x"),
+                ident("[tags=(a"),
+                TokenKind::Minus,
+                ident("b)]")
+            ]
+        );
+    }
+}
@@ -1,2 +1,20 @@
 pub mod parse;
 pub mod reader;
+
+pub mod span;
+pub use span::Span;
+
+pub mod token;
+pub use token::{Token, TokenKind, TokenStream};
+
+pub mod scanner;
+pub use scanner::{ScanError, Scanner};
+
+pub mod lexer;
+pub use lexer::{LexMode, Lexer};
+
+pub mod ast;
+pub use ast::{
+    Attribute, ContentSection, Document, ImportSection, Node, PathSep, Relation, RelationOp,
+    UseDecl, UseTree,
+};
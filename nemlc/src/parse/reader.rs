@@ -4,9 +4,11 @@ use std::{
     rc::Rc,
 };
 
-pub struct BufReader {
-    reader: io::BufReader<File>,
+pub struct BufReader<R = io::BufReader<File>> {
+    reader: R,
     buf: Rc<String>,
+    bytes: Vec<u8>,
+    pub lossy: bool,
 }
 
 fn new_buf() -> Rc<String> {
@@ -17,13 +19,25 @@ impl BufReader {
     pub fn open(path: impl AsRef<std::path::Path>) -> io::Result<Self> {
         let file = File::open(path)?;
         let reader = io::BufReader::new(file);
+
+        Ok(BufReader::new(reader))
+    }
+}
+
+impl<R: BufRead> BufReader<R> {
+    pub fn new(reader: R) -> Self {
         let buf = new_buf();
 
-        Ok(Self { reader, buf })
+        Self {
+            reader,
+            buf,
+            bytes: Vec::new(),
+            lossy: false,
+        }
     }
 }
 
-impl Iterator for BufReader {
+impl<R: BufRead> Iterator for BufReader<R> {
     type Item = io::Result<Rc<String>>;
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -38,15 +52,17 @@ impl Iterator for BufReader {
             }
         };
 
-        self.reader
-            .read_line(buf)
-            .map(|u| {
-                if u == 0 {
-                    None
-                } else {
-                    Some(Rc::clone(&self.buf))
-                }
-            })
-            .transpose()
+        self.bytes.clear();
+        match self.reader.read_until(b'\n', &mut self.bytes) {
+            Ok(0) => None,
+            Ok(_) => {
+                let text = String::from_utf8_lossy(&self.bytes);
+                self.lossy = matches!(text, std::borrow::Cow::Owned(_));
+                buf.push_str(&text);
+
+                Some(Ok(Rc::clone(&self.buf)))
+            }
+            Err(e) => Some(Err(e)),
+        }
     }
 }
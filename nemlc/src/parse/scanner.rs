@@ -0,0 +1,212 @@
+use super::span::Span;
+use crate::diag::{Code, Diagnostic};
+
+/*
+ * A char cursor over a piece of source, shared by the lexer, the
+ * attribute parser and the manifest reader. Positions count chars of
+ * `text`; spans are mapped back to the file through `origin`, the span
+ * of the first char of `text`.
+ */
+pub struct Scanner<'a> {
+    pub text: &'a str,
+    pub chars: Vec<(usize, char)>,
+    pub pos: usize,
+    origin: Span,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScanError {
+    Unterminated(usize),
+    UnknownEscape(usize),
+}
+
+impl<'a> Scanner<'a> {
+    pub fn new(text: &'a str, origin: Span) -> Self {
+        Scanner {
+            text,
+            chars: text.char_indices().collect(),
+            pos: 0,
+            origin,
+        }
+    }
+
+    pub fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).map(|&(_, c)| c)
+    }
+
+    pub fn peek_at(&self, n: usize) -> Option<char> {
+        self.chars.get(self.pos + n).map(|&(_, c)| c)
+    }
+
+    /*
+     * The byte offset into `text` of the char at `pos`.
+     */
+    pub fn byte(&self, pos: usize) -> usize {
+        self.chars.get(pos).map_or(self.text.len(), |&(b, _)| b)
+    }
+
+    pub fn span_from(&self, start: usize) -> Span {
+        let start_byte = self.byte(start);
+        let before = &self.text[..start_byte];
+        let (line, column) = match before.rfind('\n') {
+            Some(nl) => (
+                self.origin.line + before.matches('\n').count(),
+                before[nl + 1..].chars().count() + 1,
+            ),
+            None => (
+                self.origin.line,
+                self.origin.column + before.chars().count(),
+            ),
+        };
+
+        Span::new(
+            self.origin.start + start_byte,
+            self.origin.start + self.byte(self.pos),
+            line,
+            column,
+        )
+    }
+
+    /*
+     * An error from the char at `start` to the current one.
+     */
+    pub fn error(&self, code: Code, start: usize, message: impl Into<String>) -> Diagnostic {
+        Diagnostic::error(code, message, self.span_from(start))
+    }
+
+    pub fn text_from(&self, start: usize) -> &'a str {
+        &self.text[self.byte(start)..self.byte(self.pos)]
+    }
+
+    pub fn eat_while(&mut self, f: impl Fn(char) -> bool) -> &'a str {
+        let start = self.pos;
+        while self.peek().is_some_and(&f) {
+            self.pos += 1;
+        }
+
+        self.text_from(start)
+    }
+
+    /*
+     * Reads the `"` string at the current char. `escapes` maps the char
+     * after a backslash to the char it stands for; any other escape is
+     * an error when `strict` is set and kept as written otherwise. A
+     * string cannot span lines.
+     */
+    pub fn string(&mut self, escapes: &[(char, char)], strict: bool) -> Result<String, ScanError> {
+        let start = self.pos;
+        self.pos += 1;
+        let mut out = String::new();
+        loop {
+            match self.peek() {
+                None | Some('\n') => return Err(ScanError::Unterminated(start)),
+                Some('"') => {
+                    self.pos += 1;
+                    return Ok(out);
+                }
+                Some('\\') => {
+                    self.pos += 1;
+                    let escape = self
+                        .peek()
+                        .and_then(|c| escapes.iter().find(|&&(from, _)| from == c));
+                    match escape {
+                        Some(&(_, to)) => {
+                            out.push(to);
+                            self.pos += 1;
+                        }
+                        None if strict => return Err(ScanError::UnknownEscape(self.pos - 1)),
+                        None => out.push('\\'),
+                    }
+                }
+                Some(c) => {
+                    out.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+    }
+}
+
+impl ScanError {
+    /*
+     * Where the problem starts: the opening quote, or the backslash.
+     */
+    pub fn at(&self) -> usize {
+        match self {
+            ScanError::Unterminated(at) | ScanError::UnknownEscape(at) => *at,
+        }
+    }
+
+    fn message(&self) -> &'static str {
+        match self {
+            ScanError::Unterminated(_) => "unterminated string",
+            ScanError::UnknownEscape(_) => "unknown escape",
+        }
+    }
+
+    fn help(&self) -> Option<&'static str> {
+        match self {
+            ScanError::Unterminated(_) => Some("add a closing `\"`"),
+            ScanError::UnknownEscape(_) => None,
+        }
+    }
+
+    /*
+     * The error as reported by the reader that ran `scan`, under its
+     * own `code`.
+     */
+    pub fn to_diagnostic(self, code: Code, scan: &Scanner) -> Diagnostic {
+        let d = scan.error(code, self.at(), self.message());
+        match self.help() {
+            Some(help) => d.with_help(help),
+            None => d,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ESCAPES: &[(char, char)] = &[('"', '"'), ('\\', '\\'), ('n', '\n')];
+
+    #[test]
+    fn spans_follow_the_origin() {
+        let mut s = Scanner::new("ab 数据", Span::new(10, 10, 4, 7));
+        s.pos = 3;
+        s.eat_while(|c| !c.is_whitespace());
+        assert_eq!(s.span_from(3), Span::new(13, 19, 4, 10));
+    }
+
+    #[test]
+    fn spans_after_a_newline_restart_the_column() {
+        let mut s = Scanner::new("a\né = 1", Span::new(0, 0, 1, 1));
+        s.pos = 2;
+        s.eat_while(|c| c != ' ');
+        assert_eq!(s.span_from(2), Span::new(2, 4, 2, 1));
+    }
+
+    #[test]
+    fn string_maps_escapes() {
+        let mut s = Scanner::new(r#""a\"b\\c\n" rest"#, Span::default());
+        assert_eq!(s.string(ESCAPES, true), Ok("a\"b\\c\n".to_string()));
+        assert_eq!(s.peek(), Some(' '));
+    }
+
+    #[test]
+    fn unknown_escapes() {
+        let mut s = Scanner::new(r#""a\qb""#, Span::default());
+        assert_eq!(s.string(ESCAPES, true), Err(ScanError::UnknownEscape(2)));
+
+        let mut s = Scanner::new(r#""a\qb""#, Span::default());
+        assert_eq!(s.string(ESCAPES, false), Ok("a\\qb".to_string()));
+    }
+
+    #[test]
+    fn strings_end_at_the_line() {
+        let mut s = Scanner::new("x = \"ab\ncd\"", Span::default());
+        s.pos = 4;
+        assert_eq!(s.string(ESCAPES, true), Err(ScanError::Unterminated(4)));
+        assert_eq!(s.peek(), Some('\n'));
+    }
+}
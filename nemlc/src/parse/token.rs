@@ -0,0 +1,127 @@
+use super::span::Span;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenKind {
+    Section(String),
+    Pub,
+    Use,
+    As,
+    Ident(String),
+    Str(String),
+    Slash,
+    Dot,
+    LBrace,
+    RBrace,
+    Comma,
+    Plus,
+    Minus,
+    Star,
+    Comment(String),
+    Indent(String),
+    Invalid(String),
+}
+
+impl TokenKind {
+    pub fn describe(&self) -> String {
+        match self {
+            TokenKind::Section(s) => format!("section `#[{}]`", s),
+            TokenKind::Pub => "`pub`".to_string(),
+            TokenKind::Use => "`use`".to_string(),
+            TokenKind::As => "`as`".to_string(),
+            TokenKind::Ident(s) => format!("`{}`", s),
+            TokenKind::Str(s) => format!("string \"{}\"", s),
+            TokenKind::Slash => "`/`".to_string(),
+            TokenKind::Dot => "`.`".to_string(),
+            TokenKind::LBrace => "`{`".to_string(),
+            TokenKind::RBrace => "`}`".to_string(),
+            TokenKind::Comma => "`,`".to_string(),
+            TokenKind::Plus => "`+`".to_string(),
+            TokenKind::Minus => "`-`".to_string(),
+            TokenKind::Star => "`*`".to_string(),
+            TokenKind::Comment(_) => "comment".to_string(),
+            TokenKind::Indent(_) => "indentation".to_string(),
+            TokenKind::Invalid(s) => format!("`{}`", s),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: Span,
+}
+
+impl Token {
+    pub fn new(kind: TokenKind, span: Span) -> Self {
+        Token { kind, span }
+    }
+
+    pub fn is_trivia(&self) -> bool {
+        matches!(self.kind, TokenKind::Comment(_) | TokenKind::Indent(_))
+    }
+
+    pub fn is_relation(&self) -> bool {
+        matches!(
+            self.kind,
+            TokenKind::Plus | TokenKind::Minus | TokenKind::Star
+        )
+    }
+
+    pub fn text(&self) -> Option<&str> {
+        match &self.kind {
+            TokenKind::Ident(s) | TokenKind::Str(s) => Some(s.as_str()),
+            _ => None,
+        }
+    }
+}
+
+pub struct TokenStream<'a> {
+    tokens: &'a [Token],
+    pos: usize,
+}
+
+impl<'a> TokenStream<'a> {
+    pub fn new(tokens: &'a [Token]) -> Self {
+        TokenStream { tokens, pos: 0 }
+    }
+
+    pub fn peek(&self) -> Option<&'a Token> {
+        self.tokens.get(self.pos)
+    }
+
+    pub fn peek_kind(&self) -> Option<&'a TokenKind> {
+        self.peek().map(|t| &t.kind)
+    }
+
+    pub fn bump(&mut self) -> Option<&'a Token> {
+        let t = self.tokens.get(self.pos);
+        if t.is_some() {
+            self.pos += 1;
+        }
+
+        t
+    }
+
+    pub fn eat(&mut self, kind: &TokenKind) -> Option<&'a Token> {
+        match self.peek() {
+            Some(t) if &t.kind == kind => self.bump(),
+            _ => None,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pos >= self.tokens.len()
+    }
+
+    pub fn rest(&self) -> &'a [Token] {
+        &self.tokens[self.pos.min(self.tokens.len())..]
+    }
+
+    pub fn last_span(&self) -> Span {
+        if self.pos > 0 {
+            self.tokens[self.pos - 1].span
+        } else {
+            self.tokens.first().map(|t| t.span).unwrap_or_default()
+        }
+    }
+}
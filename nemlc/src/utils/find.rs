@@ -1,55 +1,70 @@
 pub struct Finder;
 
+/*
+ * All offsets taken and returned here are byte offsets into `s`, and
+ * always fall on a char boundary, so callers can slice with them.
+ */
 impl Finder {
     pub fn next_point(s: &str, off: usize, target: char, is_equal: bool) -> Result<usize, String> {
-        let ss = s.to_string();
-        let bytes = &ss[off..];
-        let bytes_new = bytes.to_string();
-        let s_bytes = bytes_new.as_bytes();
-        for (i, &item) in s_bytes.iter().enumerate() {
-            let m = target as u8;
-            if is_equal {
-                if item == m {
-                    return Ok(i);
-                }
-            } else {
-                if item != m {
-                    return Ok(i);
-                }
+        let rest = match s.get(off..) {
+            Some(r) => r,
+            None => return Err(format!("offset {} is not a char boundary", off)),
+        };
+
+        for (i, item) in rest.char_indices() {
+            if (item == target) == is_equal {
+                return Ok(i);
             }
         }
 
-        return Ok(s.len() - off);
+        Ok(rest.len())
     }
 
     pub fn next_sub(s: &str, off: usize, target: String) -> Option<usize> {
-        let l = s[off..].to_string().find(&target);
-
-        l
+        s.get(off..)?.find(&target)
     }
 
     pub fn space_count(s: &str, target: char, is_equal: bool) -> usize {
-        let ss = s.to_string();
-        let s_bytes = ss.as_bytes();
-
-        let mut count: usize = 0;
-        for (_i, &item) in s_bytes.iter().enumerate() {
-            let m = target as u8;
-            if is_equal {
-                if item == m {
-                    count += 1;
-                } else {
-                    break;
-                }
-            } else {
-                if item != m {
-                    count += 1;
-                } else {
-                    break;
-                }
-            }
-        }
+        s.find(|item| (item == target) != is_equal)
+            .unwrap_or(s.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn next_point_skips_cjk() {
+        let s = "服务 数据库";
+        let i = Finder::next_point(s, 0, ' ', true).unwrap();
+        assert_eq!(i, "服务".len());
+        assert_eq!(&s[i + 1..], "数据库");
+    }
+
+    #[test]
+    fn next_point_from_an_offset() {
+        let s = "café au lait";
+        let off = "café ".len();
+        assert_eq!(Finder::next_point(s, off, ' ', true), Ok(2));
+        assert_eq!(Finder::next_point(s, off, 'a', false), Ok(1));
+    }
+
+    #[test]
+    fn next_point_rejects_offsets_inside_a_char() {
+        assert!(Finder::next_point("é", 1, ' ', true).is_err());
+    }
+
+    #[test]
+    fn next_point_runs_to_the_end() {
+        let s = "🚀🚀";
+        assert_eq!(Finder::next_point(s, 0, ' ', true), Ok(s.len()));
+    }
 
-        return count;
+    #[test]
+    fn space_count_is_in_bytes() {
+        assert_eq!(Finder::space_count("  é", ' ', true), 2);
+        assert_eq!(Finder::space_count("éé x", ' ', false), "éé".len());
+        assert_eq!(Finder::space_count("🚀", ' ', false), 4);
     }
 }
@@ -1,50 +1,47 @@
+use super::find::Finder;
+
 pub struct Strip;
 
 impl Strip {
     pub fn rstrip(input: String, label: char) -> String {
-        let mut tot_len = input.len();
-        while tot_len > 0 {
-            if input.as_bytes()[tot_len - 1] == (label as u8) {
-                tot_len -= 1;
-            } else {
-                break;
-            }
-        }
-
-        return input[0..tot_len].to_string();
+        input.trim_end_matches(label).to_string()
     }
 
     pub fn colon_strip(input: String) -> String {
-        if input.starts_with("\"") && input.ends_with("\"") {
-            if input.len() < 3 {
-                return "".to_string();
-            }
-
-            let r = &input[1..(input.len() - 2)];
-            r.to_string()
-        } else {
-            input
+        match input.strip_prefix('"').and_then(|r| r.strip_suffix('"')) {
+            Some(r) => r.to_string(),
+            None => input,
         }
     }
 
     pub fn next_strip(s: &str, off: usize, target: char, is_equal: bool) -> Result<usize, String> {
-        let ss = s.to_string();
-        let bytes = &ss[off..];
-        let bytes_new = bytes.to_string();
-        let s_bytes = bytes_new.as_bytes();
-        for (i, &item) in s_bytes.iter().enumerate() {
-            let m = target as u8;
-            if is_equal {
-                if item != m {
-                    return Ok(i);
-                }
-            } else {
-                if item == m {
-                    return Ok(i);
-                }
-            }
-        }
+        Finder::next_point(s, off, target, !is_equal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rstrip_keeps_multibyte_text() {
+        assert_eq!(Strip::rstrip("数据库\n".to_string(), '\n'), "数据库");
+        assert_eq!(Strip::rstrip("naïve\r".to_string(), '\r'), "naïve");
+        assert_eq!(Strip::rstrip("🚀🚀".to_string(), '🚀'), "");
+    }
+
+    #[test]
+    fn next_strip_skips_leading_targets() {
+        let s = "  服务";
+        let i = Strip::next_strip(s, 0, ' ', true).unwrap();
+        assert_eq!(&s[i..], "服务");
+    }
 
-        return Ok(s.len() - off);
+    #[test]
+    fn next_strip_after_an_emoji() {
+        let s = "🚀  é";
+        let off = "🚀".len();
+        let i = Strip::next_strip(s, off, ' ', true).unwrap();
+        assert_eq!(&s[off + i..], "é");
     }
 }